@@ -1,39 +1,567 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
+    #[serde(default = "default_environment")]
     pub environment: String,
+    #[serde(default)]
     pub server: ServerConfig,
+    #[serde(default)]
     pub logging: LoggingConfig,
+    #[serde(default)]
     pub cors: CorsConfig,
+    #[serde(default)]
+    pub tls: TlsConfig,
+    #[serde(default)]
+    pub access_log: AccessLogConfig,
+    #[serde(default)]
+    pub capture: CaptureConfig,
+    #[serde(default)]
+    pub repository: RepositoryConfig,
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    #[serde(default)]
+    pub compression: CompressionConfig,
+    #[serde(default)]
+    pub security_headers: SecurityHeadersConfig,
+    #[serde(default)]
+    pub proxy: ProxyConfig,
+    #[serde(default)]
+    pub ip_filter: IpFilterConfig,
+    #[serde(default)]
+    pub static_files: StaticFilesConfig,
+    #[serde(default)]
+    pub errors: ErrorsConfig,
+    #[serde(default)]
+    pub email: EmailConfig,
+    #[serde(default)]
+    pub import: ImportConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ServerConfig {
     pub host: String,
     pub port: u16,
     pub address: String,
+    /// Address of the listener serving operational endpoints such as health checks.
+    pub admin_address: String,
+    /// How the public listener is created: "tcp", "unix" or "systemd".
+    pub listener: String,
+    pub unix_socket: UnixSocketConfig,
+    /// URL clients reach the public API at, used for links in responses, e.g.
+    /// "https://api.example.com". Empty uses the address of the public listener.
+    pub public_base_url: String,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        let host = env_var("APP_SERVER__HOST", "127.0.0.1".to_string());
+        let port = env_var("APP_SERVER__PORT", 3000);
+
+        Self {
+            address: format!("{}:{}", host, port),
+            host,
+            port,
+            admin_address: env_var("APP_SERVER__ADMIN_ADDRESS", "127.0.0.1:3001".to_string()),
+            listener: env_var("APP_SERVER__LISTENER", "tcp".to_string()),
+            unix_socket: UnixSocketConfig::default(),
+            public_base_url: env_var("APP_SERVER__PUBLIC_BASE_URL", String::new()),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UnixSocketConfig {
+    pub path: String,
+    /// Permissions applied to the socket file, in octal.
+    pub mode: String,
+}
+
+impl Default for UnixSocketConfig {
+    fn default() -> Self {
+        Self {
+            path: env_var("APP_SERVER__UNIX_SOCKET__PATH", "/run/http-example-server/http.sock".to_string()),
+            mode: env_var("APP_SERVER__UNIX_SOCKET__MODE", "660".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct LoggingConfig {
     pub level: String,
     pub json_format: bool,
+    pub redaction: RedactionConfig,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: env_var("APP_LOGGING__LEVEL", "info".to_string()),
+            json_format: env_var("APP_LOGGING__JSON_FORMAT", false),
+            redaction: RedactionConfig::default(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactionConfig {
+    /// How PII fields are logged: "off", "hash", "mask" or "drop".
+    pub mode: String,
+    /// Salt mixed into hashed values so they cannot be looked up in precomputed tables.
+    pub salt: String,
+    /// Query parameters whose values are treated as PII in logged URIs.
+    pub query_params: Vec<String>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            mode: env_var("APP_LOGGING__REDACTION__MODE", "mask".to_string()),
+            salt: env_var("APP_LOGGING__REDACTION__SALT", String::new()),
+            query_params: env_list("APP_LOGGING__REDACTION__QUERY_PARAMS", &["email", "name"]),
+        }
+    }
+}
+
+/// Access logging is independent of the application log level in `LoggingConfig`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessLogConfig {
+    pub enabled: bool,
+    pub path: String,
+    /// One of "common", "combined" or "json".
+    pub format: String,
+    /// Fields written by the "json" format, in order.
+    pub json_fields: Vec<String>,
+    /// Time-based rotation: "hourly", "daily" or "never".
+    pub rotation: String,
+    /// Rotate once the file would exceed this size; 0 disables size-based rotation.
+    pub max_size_mb: u64,
+    /// Number of rotated files to keep.
+    pub max_files: usize,
+}
+
+impl Default for AccessLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: env_var("APP_ACCESS_LOG__ENABLED", false),
+            path: env_var("APP_ACCESS_LOG__PATH", "logs/access.log".to_string()),
+            format: env_var("APP_ACCESS_LOG__FORMAT", "combined".to_string()),
+            json_fields: env_list(
+                "APP_ACCESS_LOG__JSON_FIELDS",
+                crate::telemetry::access_log::JSON_FIELDS,
+            ),
+            rotation: env_var("APP_ACCESS_LOG__ROTATION", "daily".to_string()),
+            max_size_mb: env_var("APP_ACCESS_LOG__MAX_SIZE_MB", 100),
+            max_files: env_var("APP_ACCESS_LOG__MAX_FILES", 14),
+        }
+    }
+}
+
+/// Opt-in recording of request and response bodies for debugging.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptureConfig {
+    pub enabled: bool,
+    pub routes: Vec<CaptureRoute>,
+    /// Fraction of matching requests to capture, between 0.0 and 1.0.
+    pub sample_rate: f64,
+    /// Bodies are truncated to this many bytes.
+    pub max_body_bytes: usize,
+    /// Number of entries kept in memory; the oldest are evicted first. 0 disables capture.
+    pub buffer_size: usize,
+    pub redact_headers: Vec<String>,
+    pub redact_json_fields: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureRoute {
+    pub path_prefix: String,
+    /// HTTP methods to capture; empty means all methods.
+    #[serde(default)]
+    pub methods: Vec<String>,
+    /// Overrides the global sample rate for this route.
+    #[serde(default)]
+    pub sample_rate: Option<f64>,
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: env_var("APP_CAPTURE__ENABLED", false),
+            routes: env_list("APP_CAPTURE__PATHS", &["/api/v1/users"])
+                .into_iter()
+                .map(|path_prefix| CaptureRoute {
+                    path_prefix,
+                    methods: Vec::new(),
+                    sample_rate: None,
+                })
+                .collect(),
+            sample_rate: env_var("APP_CAPTURE__SAMPLE_RATE", 1.0),
+            max_body_bytes: env_var("APP_CAPTURE__MAX_BODY_BYTES", 16 * 1024),
+            buffer_size: env_var("APP_CAPTURE__BUFFER_SIZE", 200),
+            redact_headers: env_list(
+                "APP_CAPTURE__REDACT_HEADERS",
+                &["authorization", "cookie", "set-cookie", "proxy-authorization", "x-api-key"],
+            ),
+            redact_json_fields: env_list(
+                "APP_CAPTURE__REDACT_JSON_FIELDS",
+                &["password", "token", "secret", "api_key", "email", "name"],
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RepositoryConfig {
+    /// What to do when a lock was poisoned by a panic: "recover" or "fail".
+    pub lock_poison_policy: String,
+}
+
+impl Default for RepositoryConfig {
+    fn default() -> Self {
+        Self {
+            lock_poison_policy: env_var("APP_REPOSITORY__LOCK_POISON_POLICY", "recover".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LimitsConfig {
+    /// Time allowed for a request, including time spent queued for capacity.
+    pub request_timeout_ms: u64,
+    /// Overrides the request timeout for matching routes; the first match wins.
+    pub route_timeouts: Vec<RouteTimeout>,
+    /// Requests handled at once on the public listener; 0 disables the limit.
+    pub max_concurrent_requests: usize,
+    /// Requests are rejected without queueing while the average time spent
+    /// waiting for capacity is above this threshold.
+    pub shed_queue_wait_ms: u64,
+    /// Time a client has to complete the TLS handshake.
+    pub tls_handshake_timeout_ms: u64,
+    /// Time a client has to send the headers of an HTTP/1 request.
+    pub header_read_timeout_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteTimeout {
+    pub path_prefix: String,
+    /// HTTP methods the timeout applies to; empty means all methods.
+    #[serde(default)]
+    pub methods: Vec<String>,
+    pub timeout_ms: u64,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            request_timeout_ms: env_var("APP_LIMITS__REQUEST_TIMEOUT_MS", 30_000),
+            route_timeouts: Vec::new(),
+            max_concurrent_requests: env_var("APP_LIMITS__MAX_CONCURRENT_REQUESTS", 512),
+            shed_queue_wait_ms: env_var("APP_LIMITS__SHED_QUEUE_WAIT_MS", 100),
+            tls_handshake_timeout_ms: env_var("APP_LIMITS__TLS_HANDSHAKE_TIMEOUT_MS", 10_000),
+            header_read_timeout_ms: env_var("APP_LIMITS__HEADER_READ_TIMEOUT_MS", 10_000),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompressionConfig {
+    pub enabled: bool,
+    /// Response encodings offered to clients: "gzip", "br" and/or "zstd".
+    pub algorithms: Vec<String>,
+    /// Responses smaller than this are sent uncompressed.
+    pub min_size_bytes: u16,
+    /// Content type prefixes eligible for compression.
+    pub content_types: Vec<String>,
+    /// Accept gzip, br and zstd `Content-Encoding` on request bodies.
+    pub request_decompression: bool,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: env_var("APP_COMPRESSION__ENABLED", true),
+            algorithms: env_list("APP_COMPRESSION__ALGORITHMS", &["zstd", "br", "gzip"]),
+            min_size_bytes: env_var("APP_COMPRESSION__MIN_SIZE_BYTES", 1024),
+            content_types: env_list(
+                "APP_COMPRESSION__CONTENT_TYPES",
+                &["application/json", "text/", "application/javascript", "application/xml", "image/svg+xml"],
+            ),
+            request_decompression: env_var("APP_COMPRESSION__REQUEST_DECOMPRESSION", true),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProxyConfig {
+    /// Addresses or CIDR ranges of proxies whose forwarding headers are believed.
+    pub trusted_proxies: Vec<String>,
+    /// The one header the trusted proxies set: "x-forwarded-for" (with
+    /// `X-Forwarded-Proto` and `X-Forwarded-Host`) or "forwarded" (RFC 7239).
+    /// The other header is ignored, as proxies pass it through from clients.
+    pub forwarded_header: String,
+    /// Believe forwarding headers on connections over a Unix socket, which have
+    /// no peer address to match against `trusted_proxies`.
+    pub trust_unix_socket: bool,
+}
+
+impl Default for ProxyConfig {
+    fn default() -> Self {
+        Self {
+            trusted_proxies: env_list("APP_PROXY__TRUSTED_PROXIES", &[]),
+            forwarded_header: env_var("APP_PROXY__FORWARDED_HEADER", "x-forwarded-for".to_string()),
+            trust_unix_socket: env_var("APP_PROXY__TRUST_UNIX_SOCKET", false),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IpFilterConfig {
+    /// Evaluated in order; the first rule matching the path and method decides.
+    pub rules: Vec<IpFilterRule>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpFilterRule {
+    pub path_prefix: String,
+    /// HTTP methods the rule applies to; empty means all methods.
+    #[serde(default)]
+    pub methods: Vec<String>,
+    /// Addresses or CIDR ranges that may call the route; empty allows everyone not denied.
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Addresses or CIDR ranges that are always rejected.
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+/// Header values left unset fall back to defaults for the configured
+/// `environment`; an empty string disables the header.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SecurityHeadersConfig {
+    pub enabled: bool,
+    pub hsts: Option<String>,
+    pub content_type_options: Option<String>,
+    pub frame_options: Option<String>,
+    pub referrer_policy: Option<String>,
+    pub permissions_policy: Option<String>,
+    pub content_security_policy: Option<String>,
+    /// Send the CSP as `Content-Security-Policy-Report-Only` instead of enforcing it.
+    pub csp_report_only: Option<bool>,
+    /// Replaces the CSP for matching routes; the first match wins.
+    pub csp_overrides: Vec<CspOverride>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CspOverride {
+    pub path_prefix: String,
+    pub policy: String,
+}
+
+impl Default for SecurityHeadersConfig {
+    fn default() -> Self {
+        Self {
+            enabled: env_var("APP_SECURITY_HEADERS__ENABLED", true),
+            hsts: env_opt("APP_SECURITY_HEADERS__HSTS"),
+            content_type_options: env_opt("APP_SECURITY_HEADERS__CONTENT_TYPE_OPTIONS"),
+            frame_options: env_opt("APP_SECURITY_HEADERS__FRAME_OPTIONS"),
+            referrer_policy: env_opt("APP_SECURITY_HEADERS__REFERRER_POLICY"),
+            permissions_policy: env_opt("APP_SECURITY_HEADERS__PERMISSIONS_POLICY"),
+            content_security_policy: env_opt("APP_SECURITY_HEADERS__CONTENT_SECURITY_POLICY"),
+            csp_report_only: env_opt("APP_SECURITY_HEADERS__CSP_REPORT_ONLY").and_then(|v| v.parse().ok()),
+            csp_overrides: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StaticFilesConfig {
+    pub enabled: bool,
+    /// Directory the files are served from.
+    pub dir: String,
+    /// URL path the directory is mounted at; must not overlap `/api`.
+    pub mount_path: String,
+    /// Serve `index.html` for unknown paths without a file extension, so
+    /// client-side routes of a single-page app can be reloaded.
+    pub spa_fallback: bool,
+    /// `Cache-Control` max-age for files that are not content-hashed.
+    pub max_age_secs: u64,
+    /// Path prefixes, relative to the mount path, whose files are content-hashed
+    /// and cached as immutable.
+    pub immutable_prefixes: Vec<String>,
+}
+
+impl Default for StaticFilesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: env_var("APP_STATIC_FILES__ENABLED", false),
+            dir: env_var("APP_STATIC_FILES__DIR", "public".to_string()),
+            mount_path: env_var("APP_STATIC_FILES__MOUNT_PATH", "/app".to_string()),
+            spa_fallback: env_var("APP_STATIC_FILES__SPA_FALLBACK", true),
+            max_age_secs: env_var("APP_STATIC_FILES__MAX_AGE_SECS", 3600),
+            immutable_prefixes: env_list("APP_STATIC_FILES__IMMUTABLE_PREFIXES", &["/assets/"]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ErrorsConfig {
+    /// Error body format when the client does not ask for one: "json" for the
+    /// `{ "error": ... }` envelope or "problem" for RFC 9457 problem details.
+    pub format: String,
+    /// Base URI of problem types, e.g. "https://example.com/problems/"; the error
+    /// code is appended in kebab case. Empty uses `about:blank`.
+    pub problem_type_base: String,
+}
+
+impl Default for ErrorsConfig {
+    fn default() -> Self {
+        Self {
+            format: env_var("APP_ERRORS__FORMAT", "json".to_string()),
+            problem_type_base: env_var("APP_ERRORS__PROBLEM_TYPE_BASE", String::new()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmailConfig {
+    /// File listing disposable email domains to reject, one per line; empty disables the check.
+    pub disposable_domains_path: String,
+}
+
+impl Default for EmailConfig {
+    fn default() -> Self {
+        Self {
+            disposable_domains_path: env_var("APP_EMAIL__DISPOSABLE_DOMAINS_PATH", String::new()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImportConfig {
+    /// Uploads with more rows than this run as background jobs; smaller ones
+    /// are answered when they finish.
+    pub background_threshold_rows: usize,
+    /// Largest accepted upload, after request decompression.
+    pub max_upload_bytes: usize,
+    /// Finished jobs kept for polling; the oldest are dropped first.
+    pub max_jobs: usize,
+}
+
+impl Default for ImportConfig {
+    fn default() -> Self {
+        Self {
+            background_threshold_rows: env_var("APP_IMPORT__BACKGROUND_THRESHOLD_ROWS", 1000),
+            max_upload_bytes: env_var("APP_IMPORT__MAX_UPLOAD_BYTES", 10 * 1024 * 1024),
+            max_jobs: env_var("APP_IMPORT__MAX_JOBS", 100),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CorsConfig {
     pub enabled: bool,
     pub origins: Vec<String>,
 }
 
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: env_var("APP_CORS__ENABLED", true),
+            origins: env_list("APP_CORS__ORIGINS", &["*"]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TlsConfig {
+    pub enabled: bool,
+    pub cert_path: String,
+    pub key_path: String,
+    /// Minimum accepted protocol version, either "1.2" or "1.3".
+    pub min_version: String,
+    /// ALPN protocols in order of preference.
+    pub alpn_protocols: Vec<String>,
+    /// How often the certificate files are checked for changes; 0 disables reloading.
+    pub reload_interval_secs: u64,
+    pub client_auth: ClientAuthConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClientAuthConfig {
+    /// One of "none", "optional" or "required".
+    pub mode: String,
+    /// PEM bundle of the CAs trusted to issue client certificates.
+    pub ca_path: String,
+    /// Routes that may only be called by the listed client identities.
+    pub allowlist: Vec<ClientAllowRule>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientAllowRule {
+    pub path_prefix: String,
+    /// HTTP methods the rule applies to; empty means all methods.
+    #[serde(default)]
+    pub methods: Vec<String>,
+    /// Accepted identities, matched against the subject, common name or any SAN entry.
+    pub identities: Vec<String>,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: env_var("APP_TLS__ENABLED", false),
+            cert_path: env_var("APP_TLS__CERT_PATH", "certs/server.crt".to_string()),
+            key_path: env_var("APP_TLS__KEY_PATH", "certs/server.key".to_string()),
+            min_version: env_var("APP_TLS__MIN_VERSION", "1.2".to_string()),
+            alpn_protocols: env_list("APP_TLS__ALPN_PROTOCOLS", &["h2", "http/1.1"]),
+            reload_interval_secs: env_var("APP_TLS__RELOAD_INTERVAL_SECS", 30),
+            client_auth: ClientAuthConfig::default(),
+        }
+    }
+}
+
+impl Default for ClientAuthConfig {
+    fn default() -> Self {
+        Self {
+            mode: env_var("APP_TLS__CLIENT_AUTH__MODE", "none".to_string()),
+            ca_path: env_var("APP_TLS__CLIENT_AUTH__CA_PATH", "certs/client-ca.crt".to_string()),
+            allowlist: Vec::new(),
+        }
+    }
+}
+
+impl ClientAuthConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.mode != "none"
+    }
+}
+
 impl AppConfig {
     pub fn load() -> Result<Self> {
         let mut settings = config::Config::builder()
             .add_source(config::File::with_name("config/default").required(false))
             .add_source(config::File::with_name("config/local").required(false))
-            .add_source(config::Environment::with_prefix("APP").separator("__"));
+            .add_source(environment_source());
 
         // Add environment-specific config
         if let Ok(env) = std::env::var("APP_ENVIRONMENT") {
@@ -42,48 +570,90 @@ impl AppConfig {
             );
         }
 
-        let config = settings.build()?.try_deserialize().unwrap_or_else(|_| {
-            // If deserialization fails, use default config
-            AppConfig::default()
-        });
+        // A setting that does not parse stops startup rather than falling back
+        // to defaults, which would silently drop access rules and allowlists.
+        let config = settings
+            .build()?
+            .try_deserialize()
+            .context("invalid configuration")?;
 
         Ok(config)
     }
 }
 
+/// Variables such as `APP_SERVER__PORT` override file settings. Values of list
+/// settings are split on commas; empty variables are treated as unset.
+fn environment_source() -> config::Environment {
+    LIST_KEYS.iter().fold(
+        config::Environment::with_prefix("APP")
+            .separator("__")
+            .try_parsing(true)
+            .list_separator(",")
+            .ignore_empty(true),
+        |source, key| source.with_list_parse_key(key),
+    )
+}
+
+/// Settings that take a comma-separated list when set from the environment.
+const LIST_KEYS: &[&str] = &[
+    "cors.origins",
+    "logging.redaction.query_params",
+    "access_log.json_fields",
+    "capture.redact_headers",
+    "capture.redact_json_fields",
+    "compression.algorithms",
+    "compression.content_types",
+    "proxy.trusted_proxies",
+    "static_files.immutable_prefixes",
+    "tls.alpn_protocols",
+];
+
 impl Default for AppConfig {
     fn default() -> Self {
-        let host = std::env::var("APP_SERVER__HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
-        let port = std::env::var("APP_SERVER__PORT")
-            .unwrap_or_else(|_| "3000".to_string())
-            .parse()
-            .unwrap_or(3000);
-
-        Self {
-            environment: std::env::var("APP_ENVIRONMENT").unwrap_or_else(|_| "development".to_string()),
-            server: ServerConfig {
-                address: format!("{}:{}", host, port),
-                host,
-                port,
-            },
-            logging: LoggingConfig {
-                level: std::env::var("APP_LOGGING__LEVEL").unwrap_or_else(|_| "info".to_string()),
-                json_format: std::env::var("APP_LOGGING__JSON_FORMAT")
-                    .unwrap_or_else(|_| "false".to_string())
-                    .parse()
-                    .unwrap_or(false),
-            },
-            cors: CorsConfig {
-                enabled: std::env::var("APP_CORS__ENABLED")
-                    .unwrap_or_else(|_| "true".to_string())
-                    .parse()
-                    .unwrap_or(true),
-                origins: std::env::var("APP_CORS__ORIGINS")
-                    .unwrap_or_else(|_| "*".to_string())
-                    .split(',')
-                    .map(|s| s.trim().to_string())
-                    .collect(),
-            },
+        Self {
+            environment: default_environment(),
+            server: ServerConfig::default(),
+            logging: LoggingConfig::default(),
+            cors: CorsConfig::default(),
+            tls: TlsConfig::default(),
+            access_log: AccessLogConfig::default(),
+            capture: CaptureConfig::default(),
+            repository: RepositoryConfig::default(),
+            limits: LimitsConfig::default(),
+            compression: CompressionConfig::default(),
+            security_headers: SecurityHeadersConfig::default(),
+            proxy: ProxyConfig::default(),
+            ip_filter: IpFilterConfig::default(),
+            static_files: StaticFilesConfig::default(),
+            errors: ErrorsConfig::default(),
+            email: EmailConfig::default(),
+            import: ImportConfig::default(),
         }
     }
+}
+
+fn default_environment() -> String {
+    env_var("APP_ENVIRONMENT", "development".to_string())
+}
+
+fn env_var<T: std::str::FromStr>(key: &str, default: T) -> T {
+    std::env::var(key)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+fn env_opt(key: &str) -> Option<String> {
+    std::env::var(key).ok()
+}
+
+fn env_list(key: &str, default: &[&str]) -> Vec<String> {
+    match std::env::var(key) {
+        Ok(value) => value
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect(),
+        Err(_) => default.iter().map(|s| s.to_string()).collect(),
+    }
 }
\ No newline at end of file
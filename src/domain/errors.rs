@@ -3,8 +3,10 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
-use serde_json::json;
+use serde::Serialize;
+use std::fmt;
 use thiserror::Error;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 #[derive(Debug, Error)]
@@ -18,6 +20,27 @@ pub enum AppError {
     #[error("Invalid input: {0}")]
     InvalidInput(String),
 
+    #[error("Validation failed: {}", messages(.0))]
+    Validation(Vec<FieldError>),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Not acceptable: {0}")]
+    NotAcceptable(String),
+
+    #[error("Unsupported media type: {0}")]
+    UnsupportedMediaType(String),
+
+    #[error("Service unavailable: {0}")]
+    ServiceUnavailable(String),
+
+    #[error("Request timed out: {0}")]
+    Timeout(String),
+
     #[error("Database error: {0}")]
     Database(String),
 
@@ -28,38 +51,214 @@ pub enum AppError {
     Config(String),
 }
 
+/// A validation failure of a single request field.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FieldError {
+    /// Path of the offending field, e.g. `email` or `rules[0].path_prefix`.
+    #[schema(example = "email")]
+    pub field: String,
+    /// Code of the violated rule, e.g. `required` or `max_graphemes`.
+    #[schema(example = "required")]
+    pub rule: String,
+    #[schema(example = "must not be empty")]
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, rule: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            rule: rule.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+fn messages(errors: &[FieldError]) -> String {
+    errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+}
+
+/// Machine-readable error codes returned in `error.code`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    UserNotFound,
+    UserAlreadyExists,
+    InvalidInput,
+    ValidationFailed,
+    Forbidden,
+    NotFound,
+    NotAcceptable,
+    UnsupportedMediaType,
+    ServiceUnavailable,
+    RequestTimeout,
+    DatabaseError,
+    InternalError,
+    ConfigError,
+}
+
+impl ErrorCode {
+    /// Short, human-readable summary of the problem type.
+    pub fn title(self) -> &'static str {
+        match self {
+            ErrorCode::UserNotFound => "User not found",
+            ErrorCode::UserAlreadyExists => "User already exists",
+            ErrorCode::InvalidInput => "Invalid input",
+            ErrorCode::ValidationFailed => "Validation failed",
+            ErrorCode::Forbidden => "Forbidden",
+            ErrorCode::NotFound => "Not found",
+            ErrorCode::NotAcceptable => "Not acceptable",
+            ErrorCode::UnsupportedMediaType => "Unsupported media type",
+            ErrorCode::ServiceUnavailable => "Service unavailable",
+            ErrorCode::RequestTimeout => "Request timeout",
+            ErrorCode::DatabaseError => "Database error",
+            ErrorCode::InternalError => "Internal error",
+            ErrorCode::ConfigError => "Configuration error",
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::UserNotFound => "USER_NOT_FOUND",
+            ErrorCode::UserAlreadyExists => "USER_ALREADY_EXISTS",
+            ErrorCode::InvalidInput => "INVALID_INPUT",
+            ErrorCode::ValidationFailed => "VALIDATION_FAILED",
+            ErrorCode::Forbidden => "FORBIDDEN",
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::NotAcceptable => "NOT_ACCEPTABLE",
+            ErrorCode::UnsupportedMediaType => "UNSUPPORTED_MEDIA_TYPE",
+            ErrorCode::ServiceUnavailable => "SERVICE_UNAVAILABLE",
+            ErrorCode::RequestTimeout => "REQUEST_TIMEOUT",
+            ErrorCode::DatabaseError => "DATABASE_ERROR",
+            ErrorCode::InternalError => "INTERNAL_ERROR",
+            ErrorCode::ConfigError => "CONFIG_ERROR",
+        }
+    }
+
+    /// The code in kebab case, used to build problem type URIs.
+    pub fn slug(self) -> String {
+        self.as_str().to_ascii_lowercase().replace('_', "-")
+    }
+}
+
+/// The error envelope returned by every failing endpoint.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub error: ErrorBody,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody {
+    pub code: ErrorCode,
+    pub message: String,
+    /// RFC 3339 time the error occurred.
+    pub timestamp: String,
+    /// Every violated rule, present for `VALIDATION_FAILED`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+/// RFC 9457 problem details, returned as `application/problem+json` when the
+/// client asks for it or `errors.format` is `problem`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProblemDetails {
+    /// URI identifying the problem type; `about:blank` unless `errors.problem_type_base` is set.
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    /// Path of the request that failed.
+    pub instance: String,
+    pub code: ErrorCode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+/// Attached to error responses as an extension so that `ProblemDetailsLayer`
+/// can re-render them without parsing the body.
+#[derive(Debug, Clone)]
+pub struct ErrorDetails {
+    pub code: ErrorCode,
+    pub message: String,
+    pub errors: Vec<FieldError>,
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let (status, error_message, error_code) = match &self {
-            AppError::UserNotFound(_) => (StatusCode::NOT_FOUND, self.to_string(), "USER_NOT_FOUND"),
-            AppError::UserAlreadyExists(_) => (StatusCode::CONFLICT, self.to_string(), "USER_ALREADY_EXISTS"),
-            AppError::InvalidInput(_) => (StatusCode::BAD_REQUEST, self.to_string(), "INVALID_INPUT"),
+            AppError::UserNotFound(_) => (StatusCode::NOT_FOUND, self.to_string(), ErrorCode::UserNotFound),
+            AppError::UserAlreadyExists(_) => (StatusCode::CONFLICT, self.to_string(), ErrorCode::UserAlreadyExists),
+            AppError::InvalidInput(_) => (StatusCode::BAD_REQUEST, self.to_string(), ErrorCode::InvalidInput),
+            AppError::Validation(_) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                self.to_string(),
+                ErrorCode::ValidationFailed,
+            ),
+            AppError::Forbidden(_) => (StatusCode::FORBIDDEN, self.to_string(), ErrorCode::Forbidden),
+            AppError::NotFound(_) => (StatusCode::NOT_FOUND, self.to_string(), ErrorCode::NotFound),
+            AppError::NotAcceptable(_) => (StatusCode::NOT_ACCEPTABLE, self.to_string(), ErrorCode::NotAcceptable),
+            AppError::UnsupportedMediaType(_) => (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                self.to_string(),
+                ErrorCode::UnsupportedMediaType,
+            ),
+            AppError::ServiceUnavailable(_) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Service is temporarily overloaded, retry later".to_string(),
+                ErrorCode::ServiceUnavailable,
+            ),
+            AppError::Timeout(_) => (
+                StatusCode::GATEWAY_TIMEOUT,
+                "Request took too long to process".to_string(),
+                ErrorCode::RequestTimeout,
+            ),
             AppError::Database(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Database operation failed".to_string(),
-                "DATABASE_ERROR",
+                ErrorCode::DatabaseError,
             ),
             AppError::Internal(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Internal server error occurred".to_string(),
-                "INTERNAL_ERROR",
+                ErrorCode::InternalError,
             ),
             AppError::Config(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Configuration error".to_string(),
-                "CONFIG_ERROR",
+                ErrorCode::ConfigError,
             ),
         };
 
-        let body = Json(json!({
-            "error": {
-                "code": error_code,
-                "message": error_message,
-                "timestamp": chrono::Utc::now().to_rfc3339()
-            }
-        }));
+        let errors = match self {
+            AppError::Validation(errors) => errors,
+            _ => Vec::new(),
+        };
+        let details = ErrorDetails {
+            code: error_code,
+            message: error_message.clone(),
+            errors: errors.clone(),
+        };
+        let body = Json(ErrorResponse {
+            error: ErrorBody {
+                code: error_code,
+                message: error_message,
+                timestamp: chrono::Utc::now().to_rfc3339(),
+                errors,
+            },
+        });
 
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        response.extensions_mut().insert(details);
+        response
     }
 }
 
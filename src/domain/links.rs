@@ -0,0 +1,80 @@
+use crate::config::AppConfig;
+use crate::domain::requests::ListUsersQuery;
+use anyhow::{bail, Context as _, Result};
+use axum::http::Uri;
+use uuid::Uuid;
+
+/// Builds the absolute URLs of API resources used in links.
+#[derive(Debug, Clone)]
+pub struct ApiUrls {
+    /// Scheme, authority and optional path prefix, without a trailing slash.
+    base: String,
+}
+
+impl ApiUrls {
+    /// Uses `server.public_base_url`, or the address of the public listener when
+    /// it is empty. Unix socket and systemd listeners have no address clients
+    /// could use, so they require the base URL.
+    pub fn from_config(config: &AppConfig) -> Result<Self> {
+        if !config.server.public_base_url.is_empty() {
+            return Self::new(&config.server.public_base_url);
+        }
+        if config.server.listener != "tcp" {
+            bail!(
+                "server.public_base_url must be set when server.listener is {}",
+                config.server.listener
+            );
+        }
+
+        let scheme = if config.tls.enabled { "https" } else { "http" };
+        let host = match config.server.host.as_str() {
+            "0.0.0.0" | "::" => "localhost".to_string(),
+            host if host.contains(':') => format!("[{}]", host),
+            host => host.to_string(),
+        };
+        Self::new(&format!("{}://{}:{}", scheme, host, config.server.port))
+    }
+
+    pub fn new(base: &str) -> Result<Self> {
+        let uri: Uri = base
+            .parse()
+            .with_context(|| format!("Invalid public base URL: {}", base))?;
+        if !matches!(uri.scheme_str(), Some("http" | "https")) || uri.authority().is_none() || uri.query().is_some() {
+            bail!("Public base URL must be an http or https URL without a query: {}", base);
+        }
+        Ok(Self {
+            base: base.trim_end_matches('/').to_string(),
+        })
+    }
+
+    pub fn users(&self) -> String {
+        format!("{}/api/v1/users", self.base)
+    }
+
+    /// A page of the user list with the parameters of `query` at `offset`.
+    pub fn users_page(&self, query: &ListUsersQuery, offset: usize) -> String {
+        let page = ListUsersQuery {
+            limit: Some(query.get_limit()),
+            offset: Some(offset),
+            ..query.clone()
+        };
+        let query = serde_urlencoded::to_string(&page).expect("list query has only scalar fields");
+        format!("{}?{}", self.users(), query)
+    }
+
+    pub fn user(&self, id: Uuid) -> String {
+        format!("{}/{}", self.users(), id)
+    }
+
+    pub fn user_profile(&self, id: Uuid) -> String {
+        format!("{}/profile", self.user(id))
+    }
+
+    pub fn import_job(&self, id: Uuid) -> String {
+        format!("{}/import/jobs/{}", self.users(), id)
+    }
+
+    pub fn import_job_errors(&self, id: Uuid) -> String {
+        format!("{}/errors", self.import_job(id))
+    }
+}
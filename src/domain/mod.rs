@@ -1,4 +1,6 @@
 pub mod entities;
 pub mod errors;
+pub mod links;
 pub mod requests;
-pub mod responses;
\ No newline at end of file
+pub mod responses;
+pub mod validation;
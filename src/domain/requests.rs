@@ -1,83 +1,79 @@
+use crate::config::IpFilterRule;
+use crate::domain::errors::{AppError, AppResult};
+use crate::domain::validation::{Validate, Validator};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CreateUserRequest {
+    /// At most 100 characters, counted as grapheme clusters.
+    #[schema(example = "Jane Doe")]
     pub name: String,
+    #[schema(max_length = 254, example = "jane@example.com")]
     pub email: String,
+    #[schema(maximum = 150, example = 32)]
     pub age: u32,
 }
 
-impl CreateUserRequest {
-    pub fn validate(&self) -> Result<(), String> {
-        if self.name.trim().is_empty() {
-            return Err("Name cannot be empty".to_string());
-        }
-
-        if self.name.len() > 100 {
-            return Err("Name cannot exceed 100 characters".to_string());
-        }
-
-        if !self.email.contains('@') || self.email.len() < 5 {
-            return Err("Invalid email format".to_string());
-        }
-
-        if self.email.len() > 255 {
-            return Err("Email cannot exceed 255 characters".to_string());
-        }
-
-        if self.age > 150 {
-            return Err("Age must be realistic".to_string());
-        }
-
-        Ok(())
+impl Validate for CreateUserRequest {
+    fn rules(&self, v: &mut Validator) {
+        v.field("name", self.name.as_str())
+            .required()
+            .max_graphemes(100)
+            .max_combining_marks(8)
+            .plain_text();
+        v.field("email", self.email.as_str()).required().email();
+        v.field("age", &self.age).max(150);
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Fields left out are not changed; at least one must be present.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UpdateUserRequest {
+    /// At most 100 characters, counted as grapheme clusters.
     pub name: Option<String>,
+    #[schema(max_length = 254)]
     pub email: Option<String>,
+    #[schema(maximum = 150)]
     pub age: Option<u32>,
 }
 
-impl UpdateUserRequest {
-    pub fn validate(&self) -> Result<(), String> {
-        if let Some(ref name) = self.name {
-            if name.trim().is_empty() {
-                return Err("Name cannot be empty".to_string());
-            }
-            if name.len() > 100 {
-                return Err("Name cannot exceed 100 characters".to_string());
-            }
-        }
-
-        if let Some(ref email) = self.email {
-            if !email.contains('@') || email.len() < 5 {
-                return Err("Invalid email format".to_string());
-            }
-            if email.len() > 255 {
-                return Err("Email cannot exceed 255 characters".to_string());
-            }
-        }
-
-        if let Some(age) = self.age {
-            if age > 150 {
-                return Err("Age must be realistic".to_string());
-            }
-        }
-
-        Ok(())
+impl Validate for UpdateUserRequest {
+    fn rules(&self, v: &mut Validator) {
+        v.optional("name", self.name.as_deref())
+            .required()
+            .max_graphemes(100)
+            .max_combining_marks(8)
+            .plain_text();
+        v.optional("email", self.email.as_deref()).required().email();
+        v.optional("age", self.age.as_ref()).max(150);
     }
+}
 
+impl UpdateUserRequest {
     pub fn has_updates(&self) -> bool {
         self.name.is_some() || self.email.is_some() || self.age.is_some()
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ListUsersQuery {
+    /// Page size, between 1 and 100 (default 10).
+    #[param(minimum = 1, maximum = 100)]
     pub limit: Option<usize>,
+    /// Number of users to skip (default 0).
     pub offset: Option<usize>,
+    /// BCP 47 language tag whose collation orders the users by name, e.g. `sv` or
+    /// `de-u-co-phonebk`. Defaults to the language-neutral Unicode collation.
+    #[param(example = "sv")]
+    pub locale: Option<String>,
+    /// Comma-separated user fields to return, from `id`, `name`, `email` and `age`. Defaults to all.
+    #[param(example = "id,name")]
+    pub fields: Option<String>,
+    /// `profile` embeds each user's profile data.
+    #[param(example = "profile")]
+    pub expand: Option<String>,
 }
 
 impl Default for ListUsersQuery {
@@ -85,21 +81,21 @@ impl Default for ListUsersQuery {
         Self {
             limit: Some(10),
             offset: Some(0),
+            locale: None,
+            fields: None,
+            expand: None,
         }
     }
 }
 
-impl ListUsersQuery {
-    pub fn validate(&self) -> Result<(), String> {
-        if let Some(limit) = self.limit {
-            if limit == 0 || limit > 100 {
-                return Err("Limit must be between 1 and 100".to_string());
-            }
-        }
-
-        Ok(())
+impl Validate for ListUsersQuery {
+    fn rules(&self, v: &mut Validator) {
+        v.optional("limit", self.limit.as_ref()).range(1, 100);
+        v.optional("locale", self.locale.as_deref()).locale();
     }
+}
 
+impl ListUsersQuery {
     pub fn get_limit(&self) -> usize {
         self.limit.unwrap_or(10).min(100)
     }
@@ -107,4 +103,175 @@ impl ListUsersQuery {
     pub fn get_offset(&self) -> usize {
         self.offset.unwrap_or(0)
     }
-}
\ No newline at end of file
+
+    pub fn view(&self) -> AppResult<UserView> {
+        UserView::parse(self.fields.as_deref(), self.expand.as_deref())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetUserQuery {
+    /// Comma-separated user fields to return, from `id`, `name`, `email` and `age`. Defaults to all.
+    #[param(example = "id,name")]
+    pub fields: Option<String>,
+    /// `profile` embeds the user's profile data.
+    #[param(example = "profile")]
+    pub expand: Option<String>,
+}
+
+impl GetUserQuery {
+    pub fn view(&self) -> AppResult<UserView> {
+        UserView::parse(self.fields.as_deref(), self.expand.as_deref())
+    }
+}
+
+/// Fields a client can select with `fields`.
+pub const USER_FIELDS: [&str; 4] = ["id", "name", "email", "age"];
+
+/// Related data a client can embed with `expand`.
+pub const USER_EXPANSIONS: [&str; 1] = ["profile"];
+
+/// The user fields and embedded data a client asked for with `fields` and `expand`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserView {
+    /// `None` selects every field.
+    fields: Option<Vec<&'static str>>,
+    pub expand_profile: bool,
+}
+
+impl UserView {
+    /// Parses comma-separated lists, rejecting names that are not fields or
+    /// expansions so that typos do not silently return the wrong shape.
+    pub fn parse(fields: Option<&str>, expand: Option<&str>) -> AppResult<Self> {
+        let fields = fields
+            .map(|fields| {
+                let selected = names(fields, &USER_FIELDS, "field")?;
+                if selected.is_empty() {
+                    return Err(AppError::InvalidInput("fields must name at least one field".to_string()));
+                }
+                Ok(selected)
+            })
+            .transpose()?;
+        let expand = expand.map(|expand| names(expand, &USER_EXPANSIONS, "expansion")).transpose()?;
+
+        Ok(Self {
+            fields,
+            expand_profile: expand.is_some_and(|expand| expand.contains(&"profile")),
+        })
+    }
+
+    pub fn includes(&self, field: &str) -> bool {
+        self.fields.as_ref().is_none_or(|fields| fields.contains(&field))
+    }
+}
+
+fn names(list: &str, known: &[&'static str], kind: &str) -> AppResult<Vec<&'static str>> {
+    let mut selected = Vec::new();
+    for name in list.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        let name = known.iter().copied().find(|known| *known == name).ok_or_else(|| {
+            AppError::InvalidInput(format!("Unknown {} '{}'; expected one of {}", kind, name, known.join(", ")))
+        })?;
+        if !selected.contains(&name) {
+            selected.push(name);
+        }
+    }
+    Ok(selected)
+}
+
+/// User fields an import maps source columns to.
+pub const IMPORT_FIELDS: [&str; 3] = ["name", "email", "age"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// Rows whose email address is already registered are rejected.
+    #[default]
+    Create,
+    /// Rows whose email address is already registered update that user's name and age.
+    Upsert,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportUsersQuery {
+    /// Check every row and count what would change without writing anything.
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    #[param(inline)]
+    pub mode: ImportMode,
+    /// Maps source columns to user fields as `source:field` pairs. Columns named
+    /// after a field (case-insensitively) need no mapping; other columns are ignored.
+    #[param(example = "Full Name:name,E-mail:email")]
+    pub columns: Option<String>,
+}
+
+impl ImportUsersQuery {
+    /// `(source column, field)` pairs of the `columns` mapping.
+    pub fn column_mapping(&self) -> Vec<(&str, &str)> {
+        self.columns
+            .iter()
+            .flat_map(|columns| columns.split(','))
+            .filter_map(|entry| entry.rsplit_once(':'))
+            .map(|(source, field)| (source.trim(), field.trim()))
+            .collect()
+    }
+}
+
+impl Validate for ImportUsersQuery {
+    fn rules(&self, v: &mut Validator) {
+        let Some(columns) = &self.columns else {
+            return;
+        };
+        for entry in columns.split(',') {
+            let valid = entry
+                .rsplit_once(':')
+                .is_some_and(|(source, field)| !source.trim().is_empty() && IMPORT_FIELDS.contains(&field.trim()));
+            if !valid {
+                v.error(
+                    "columns",
+                    "column_mapping",
+                    format!("'{}' must be 'source:field' with field one of name, email, age", entry.trim()),
+                );
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateLogLevelRequest {
+    /// `EnvFilter` directives, e.g. `http_example_server=info,http_example_server::services::user=debug`.
+    pub directives: String,
+    /// Revert to the startup directives after this many minutes.
+    pub revert_after_minutes: Option<u64>,
+}
+
+impl Validate for UpdateLogLevelRequest {
+    fn rules(&self, v: &mut Validator) {
+        v.field("directives", self.directives.as_str()).required();
+        v.optional("revert_after_minutes", self.revert_after_minutes.as_ref())
+            .range(1, 24 * 60);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateIpFilterRequest {
+    /// Replaces all active rules; an empty list disables filtering.
+    pub rules: Vec<IpFilterRule>,
+}
+
+impl Validate for UpdateIpFilterRequest {
+    fn rules(&self, v: &mut Validator) {
+        v.each("rules", &self.rules);
+    }
+}
+
+impl Validate for IpFilterRule {
+    fn rules(&self, v: &mut Validator) {
+        v.field("path_prefix", self.path_prefix.as_str()).starts_with("/");
+        if self.allow.is_empty() && self.deny.is_empty() {
+            v.error("", "allow_or_deny", "must allow or deny at least one range");
+        }
+    }
+}
@@ -1,27 +1,80 @@
+use crate::config::IpFilterRule;
 use crate::domain::entities::User;
+use crate::domain::links::ApiUrls;
+use crate::domain::requests::{ImportMode, ListUsersQuery, UserView};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A user. Fields left out of `fields` are omitted.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UserResponse {
-    pub id: Uuid,
-    pub name: String,
-    pub email: String,
-    pub age: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub age: Option<u32>,
+    /// Present with `expand=profile`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<UserProfileExpansion>,
+    /// Always present, whatever `fields` selects.
+    pub links: UserLinks,
 }
 
-impl From<User> for UserResponse {
-    fn from(user: User) -> Self {
+impl UserResponse {
+    /// The user with every field and no embedded data.
+    pub fn new(user: User, urls: &ApiUrls) -> Self {
+        Self::render(user, &UserView::default(), urls)
+    }
+
+    /// The user with the fields and embedded data selected by `view`.
+    pub fn render(user: User, view: &UserView, urls: &ApiUrls) -> Self {
+        let profile = view.expand_profile.then(|| UserProfileExpansion::from(&user));
+        let links = UserLinks {
+            self_link: urls.user(user.id),
+            profile: urls.user_profile(user.id),
+        };
         Self {
-            id: user.id,
-            name: user.name,
-            email: user.email,
-            age: user.age,
+            id: view.includes("id").then_some(user.id),
+            name: view.includes("name").then_some(user.name),
+            email: view.includes("email").then_some(user.email),
+            age: view.includes("age").then_some(user.age),
+            profile,
+            links,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserLinks {
+    #[serde(rename = "self")]
+    #[schema(example = "https://api.example.com/api/v1/users/0b6c...")]
+    pub self_link: String,
+    #[schema(example = "https://api.example.com/api/v1/users/0b6c.../profile")]
+    pub profile: String,
+}
+
+/// The profile data of a user embedded with `expand=profile`, without the
+/// user fields it shares with `UserResponse`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserProfileExpansion {
+    pub created_at: String,
+    pub is_adult: bool,
+}
+
+impl From<&User> for UserProfileExpansion {
+    fn from(user: &User) -> Self {
+        Self {
+            created_at: "2024-01-01T00:00:00Z".to_string(), // Mock timestamp
+            is_adult: user.is_adult(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UserProfileResponse {
     pub id: Uuid,
     pub name: String,
@@ -32,26 +85,129 @@ pub struct UserProfileResponse {
     pub is_adult: bool,
 }
 
-impl From<User> for UserProfileResponse {
-    fn from(user: User) -> Self {
+impl UserProfileResponse {
+    pub fn new(user: User, urls: &ApiUrls) -> Self {
+        let profile = UserProfileExpansion::from(&user);
         Self {
+            profile_url: urls.user_profile(user.id),
             id: user.id,
-            name: user.name.clone(),
-            email: user.email.clone(),
+            name: user.name,
+            email: user.email,
             age: user.age,
-            profile_url: format!("/api/v1/users/{}/profile", user.id),
-            created_at: "2024-01-01T00:00:00Z".to_string(), // Mock timestamp
-            is_adult: user.is_adult(),
+            created_at: profile.created_at,
+            is_adult: profile.is_adult,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UsersListResponse {
     pub users: Vec<UserResponse>,
     pub total: usize,
     pub limit: usize,
     pub offset: usize,
+    pub links: PageLinks,
+}
+
+/// Pages of a listing with the same parameters; also sent as an RFC 8288 `Link` header.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PageLinks {
+    #[serde(rename = "self")]
+    pub self_link: String,
+    pub first: String,
+    /// Left out on the first page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prev: Option<String>,
+    /// Left out on the last page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+    pub last: String,
+}
+
+impl PageLinks {
+    pub fn new(urls: &ApiUrls, query: &ListUsersQuery, total: usize) -> Self {
+        let limit = query.get_limit().max(1);
+        let offset = query.get_offset();
+        let last = total.saturating_sub(1) / limit * limit;
+        let page = |offset| urls.users_page(query, offset);
+
+        Self {
+            self_link: page(offset),
+            first: page(0),
+            // Past the end, the previous page is the last one that has users
+            prev: (offset > 0).then(|| page(offset.saturating_sub(limit).min(last))),
+            next: (offset + limit < total).then(|| page(offset + limit)),
+            last: page(last),
+        }
+    }
+
+    /// The navigation links as the value of a `Link` header.
+    pub fn to_header(&self) -> String {
+        [
+            ("first", Some(&self.first)),
+            ("prev", self.prev.as_ref()),
+            ("next", self.next.as_ref()),
+            ("last", Some(&self.last)),
+        ]
+        .into_iter()
+        .filter_map(|(rel, url)| url.map(|url| format!("<{}>; rel=\"{}\"", url, rel)))
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportJobStatus {
+    Queued,
+    Running,
+    Completed,
+    /// Stopped before every row was processed; rows before the failure were imported.
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImportJobResponse {
+    pub id: Uuid,
+    pub status: ImportJobStatus,
+    pub dry_run: bool,
+    pub mode: ImportMode,
+    /// Data rows in the upload, not counting the CSV header and blank lines.
+    pub total_rows: usize,
+    pub processed_rows: usize,
+    /// Rows that created a user, or would have in a dry run.
+    pub created: usize,
+    /// Rows that updated an existing user, or would have in a dry run.
+    pub updated: usize,
+    /// Rows rejected; each has at least one entry in the error report.
+    pub failed: usize,
+    /// Why the job stopped, when `status` is `failed`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: String,
+    pub finished_at: Option<String>,
+    pub links: ImportJobLinks,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImportJobLinks {
+    #[serde(rename = "self")]
+    pub self_link: String,
+    /// Line-numbered report of the rejected rows, as CSV or NDJSON.
+    pub errors: String,
+}
+
+/// A rejected row of an import; a row can have several.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImportRowError {
+    /// Line of the upload the row starts on; the CSV header is line 1.
+    pub line: u64,
+    #[schema(example = "email")]
+    pub field: String,
+    #[schema(example = "required")]
+    pub rule: String,
+    #[schema(example = "must not be empty")]
+    pub message: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -74,6 +230,19 @@ impl HealthResponse {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogLevelResponse {
+    pub directives: String,
+    pub default_directives: String,
+    pub revert_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpFilterResponse {
+    pub rules: Vec<IpFilterRule>,
+    pub default_rules: Vec<IpFilterRule>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ApiResponse<T> {
     pub data: T,
     pub timestamp: String,
@@ -0,0 +1,310 @@
+use crate::domain::errors::FieldError;
+use crate::utils::{email::EmailAddress, text};
+use icu_locid::Locale;
+use std::fmt::Display;
+
+/// Implemented by request DTOs. `rules` declares the constraints of every
+/// field; `validate` checks all of them and reports every violation at once.
+///
+/// ```ignore
+/// impl Validate for CreateUserRequest {
+///     fn rules(&self, v: &mut Validator) {
+///         v.field("name", self.name.as_str()).required().max_graphemes(100);
+///         v.field("age", &self.age).max(150);
+///     }
+/// }
+/// ```
+pub trait Validate {
+    fn rules(&self, v: &mut Validator);
+
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut validator = Validator::default();
+        self.rules(&mut validator);
+        validator.finish()
+    }
+}
+
+/// Collects violations while the rules of a DTO are evaluated.
+#[derive(Debug, Default)]
+pub struct Validator {
+    prefix: String,
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    /// Starts the rules of a field that must be present.
+    pub fn field<'v, T: ?Sized>(&'v mut self, name: &str, value: &'v T) -> Field<'v, T> {
+        self.optional(name, Some(value))
+    }
+
+    /// Starts the rules of a field that may be left out; they only apply when
+    /// a value is given.
+    pub fn optional<'v, T: ?Sized>(&'v mut self, name: &str, value: Option<&'v T>) -> Field<'v, T> {
+        let path = self.path(name);
+        Field {
+            validator: self,
+            path,
+            value,
+            failed: false,
+        }
+    }
+
+    /// Validates a nested DTO, reporting its fields as `name.field`.
+    pub fn nested<T: Validate>(&mut self, name: &str, value: &T) {
+        let path = self.path(name);
+        let parent = std::mem::replace(&mut self.prefix, path);
+        value.rules(self);
+        self.prefix = parent;
+    }
+
+    /// Validates every element of a list, reporting fields as `name[index].field`.
+    pub fn each<T: Validate>(&mut self, name: &str, values: &[T]) {
+        for (index, value) in values.iter().enumerate() {
+            self.nested(&format!("{}[{}]", name, index), value);
+        }
+    }
+
+    /// Records a violation that does not fit a field rule, e.g. one spanning several fields.
+    pub fn error(&mut self, name: &str, rule: &str, message: impl Into<String>) {
+        let path = self.path(name);
+        self.errors.push(FieldError::new(&path, rule, message));
+    }
+
+    pub fn finish(self) -> Result<(), Vec<FieldError>> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self.errors)
+        }
+    }
+
+    fn path(&self, name: &str) -> String {
+        match (self.prefix.is_empty(), name.is_empty()) {
+            (true, _) => name.to_string(),
+            (false, true) => self.prefix.clone(),
+            (false, false) => format!("{}.{}", self.prefix, name),
+        }
+    }
+}
+
+/// The rules of one field. Only the first failing rule of a field is
+/// reported, so an empty email is not also reported as malformed.
+pub struct Field<'v, T: ?Sized> {
+    validator: &'v mut Validator,
+    path: String,
+    value: Option<&'v T>,
+    failed: bool,
+}
+
+impl<'v, T: ?Sized> Field<'v, T> {
+    /// A custom rule: `valid` decides whether the value passes.
+    pub fn check(mut self, rule: &str, message: impl Into<String>, valid: impl FnOnce(&T) -> bool) -> Self {
+        if let Some(value) = self.value.filter(|_| !self.failed) {
+            if !valid(value) {
+                self.validator.errors.push(FieldError::new(&self.path, rule, message));
+                self.failed = true;
+            }
+        }
+        self
+    }
+}
+
+impl Field<'_, str> {
+    /// Rejects empty and whitespace-only strings.
+    pub fn required(self) -> Self {
+        self.check("required", "must not be empty", |value| !value.trim().is_empty())
+    }
+
+    /// Limits the length in user-perceived characters (grapheme clusters) of
+    /// the NFC-normalized value; use for text people type, such as names.
+    ///
+    /// A grapheme can carry any number of combining marks, so pair this with
+    /// `max_combining_marks` to bound the size of the value.
+    pub fn max_graphemes(self, max: usize) -> Self {
+        self.check("max_graphemes", format!("must be at most {} characters", max), |value| {
+            text::grapheme_count(&text::normalize(value)) <= max
+        })
+    }
+
+    /// Limits the combining marks stacked on any one character of the
+    /// NFC-normalized value.
+    pub fn max_combining_marks(self, max: usize) -> Self {
+        self.check(
+            "max_combining_marks",
+            format!("must not stack more than {} combining marks on one character", max),
+            |value| text::max_marks_per_grapheme(&text::normalize(value)) <= max,
+        )
+    }
+
+    /// Rejects control characters and bidirectional formatting characters.
+    pub fn plain_text(self) -> Self {
+        match self.value.and_then(text::find_forbidden_char) {
+            Some(c) => {
+                let kind = if c.is_control() { "control" } else { "bidirectional formatting" };
+                self.check(
+                    "forbidden_character",
+                    format!("must not contain the {} character U+{:04X}", kind, c as u32),
+                    |_| false,
+                )
+            }
+            None => self,
+        }
+    }
+
+    /// Checks the syntax of an email address; see `EmailAddress::parse`.
+    pub fn email(self) -> Self {
+        match self.value.and_then(|value| EmailAddress::parse(value).err()) {
+            Some(error) => self.check("email", error.to_string(), |_| false),
+            None => self,
+        }
+    }
+
+    /// Requires a well-formed BCP 47 language tag such as `sv` or `de-u-co-phonebk`.
+    pub fn locale(self) -> Self {
+        self.check("locale", "must be a BCP 47 language tag", |value| value.parse::<Locale>().is_ok())
+    }
+
+    pub fn starts_with(self, prefix: &str) -> Self {
+        self.check("starts_with", format!("must start with '{}'", prefix), |value| {
+            value.starts_with(prefix)
+        })
+    }
+}
+
+impl<N: PartialOrd + Display + Copy> Field<'_, N> {
+    pub fn max(self, max: N) -> Self {
+        self.check("max", format!("must be at most {}", max), |value| *value <= max)
+    }
+
+    pub fn range(self, min: N, max: N) -> Self {
+        self.check("range", format!("must be between {} and {}", min, max), |value| {
+            *value >= min && *value <= max
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::IpFilterRule;
+    use crate::domain::requests::{CreateUserRequest, UpdateIpFilterRequest, UpdateUserRequest};
+
+    fn fields(errors: &[FieldError]) -> Vec<(&str, &str)> {
+        errors.iter().map(|e| (e.field.as_str(), e.rule.as_str())).collect()
+    }
+
+    fn ip_rule(path_prefix: &str, allow: &[&str]) -> IpFilterRule {
+        IpFilterRule {
+            path_prefix: path_prefix.to_string(),
+            methods: Vec::new(),
+            allow: allow.iter().map(|a| a.to_string()).collect(),
+            deny: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn collects_a_violation_for_every_failing_field() {
+        let request = CreateUserRequest {
+            name: " ".to_string(),
+            email: "not-an-email".to_string(),
+            age: 200,
+        };
+
+        let errors = request.validate().unwrap_err();
+
+        assert_eq!(fields(&errors), [("name", "required"), ("email", "email"), ("age", "max")]);
+    }
+
+    #[test]
+    fn reports_only_the_first_failing_rule_of_a_field() {
+        let request = CreateUserRequest {
+            name: String::new(),
+            email: String::new(),
+            age: 30,
+        };
+
+        let errors = request.validate().unwrap_err();
+
+        assert_eq!(fields(&errors), [("name", "required"), ("email", "required")]);
+    }
+
+    #[test]
+    fn reports_nested_list_fields_with_their_index() {
+        let request = UpdateIpFilterRequest {
+            rules: vec![
+                ip_rule("api", &["10.0.0.0/8"]),
+                ip_rule("/ok", &["10.0.0.0/8"]),
+                ip_rule("/admin", &[]),
+            ],
+        };
+
+        let errors = request.validate().unwrap_err();
+
+        assert_eq!(
+            fields(&errors),
+            [("rules[0].path_prefix", "starts_with"), ("rules[2]", "allow_or_deny")]
+        );
+    }
+
+    #[test]
+    fn nested_paths_do_not_leak_into_later_fields() {
+        let mut v = Validator::default();
+        v.each("rules", &[ip_rule("api", &["10.0.0.0/8"])]);
+        v.field("name", "").required();
+
+        let errors = v.finish().unwrap_err();
+
+        assert_eq!(fields(&errors), [("rules[0].path_prefix", "starts_with"), ("name", "required")]);
+    }
+
+    #[test]
+    fn skips_optional_fields_that_are_left_out() {
+        let request = UpdateUserRequest {
+            name: None,
+            email: None,
+            age: None,
+        };
+
+        assert!(request.validate().is_ok());
+    }
+
+    #[test]
+    fn checks_optional_fields_that_are_given() {
+        let request = UpdateUserRequest {
+            name: None,
+            email: Some("nope".to_string()),
+            age: Some(151),
+        };
+
+        let errors = request.validate().unwrap_err();
+
+        assert_eq!(fields(&errors), [("email", "email"), ("age", "max")]);
+    }
+
+    #[test]
+    fn max_graphemes_counts_clusters() {
+        let check = |value: &str| {
+            let mut v = Validator::default();
+            v.field("name", value).max_graphemes(3);
+            v.finish()
+        };
+
+        assert!(check("e\u{301}e\u{301}e\u{301}").is_ok());
+        assert!(check("\u{915}\u{94d}\u{937}\u{93f}\u{924}\u{93f}").is_ok());
+        assert!(check(&"\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}\u{200d}\u{1f466}".repeat(3)).is_ok());
+        assert!(check("abcd").is_err());
+    }
+
+    #[test]
+    fn max_combining_marks_limits_marks_per_character() {
+        let check = |value: &str| {
+            let mut v = Validator::default();
+            v.field("name", value).max_combining_marks(4);
+            v.finish()
+        };
+
+        assert!(check("\u{938}\u{94d}\u{924}\u{94d}\u{930}\u{940}").is_ok());
+        assert!(check(&format!("a{}", "\u{301}\u{302}".repeat(2))).is_ok());
+        assert!(check(&format!("a{}", "\u{301}".repeat(20))).is_err());
+    }
+}
@@ -0,0 +1,99 @@
+use crate::domain::{
+    errors::{AppError, AppResult},
+    requests::{UpdateIpFilterRequest, UpdateLogLevelRequest},
+    responses::{ApiResponse, IpFilterResponse, LogLevelResponse},
+    validation::Validate,
+};
+use crate::middleware::ip_filter::IpFilter;
+use crate::telemetry::{capture::CaptureEntry, log_level::LogLevelController};
+use crate::AdminState;
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json},
+};
+use std::time::Duration;
+use uuid::Uuid;
+
+pub async fn get_log_level(State(state): State<AdminState>) -> Json<ApiResponse<LogLevelResponse>> {
+    Json(ApiResponse::new(log_level_response(&state.log_level)))
+}
+
+pub async fn update_log_level(
+    State(state): State<AdminState>,
+    Json(payload): Json<UpdateLogLevelRequest>,
+) -> AppResult<Json<ApiResponse<LogLevelResponse>>> {
+    payload.validate().map_err(AppError::Validation)?;
+
+    let revert_after = payload
+        .revert_after_minutes
+        .map(|minutes| Duration::from_secs(minutes * 60));
+    state.log_level.set(payload.directives.trim(), revert_after)?;
+
+    Ok(Json(ApiResponse::new(log_level_response(&state.log_level))))
+}
+
+pub async fn reset_log_level(State(state): State<AdminState>) -> AppResult<Json<ApiResponse<LogLevelResponse>>> {
+    state.log_level.reset()?;
+    Ok(Json(ApiResponse::new(log_level_response(&state.log_level))))
+}
+
+pub async fn get_ip_filter(State(state): State<AdminState>) -> Json<ApiResponse<IpFilterResponse>> {
+    Json(ApiResponse::new(ip_filter_response(&state.ip_filter)))
+}
+
+pub async fn update_ip_filter(
+    State(state): State<AdminState>,
+    Json(payload): Json<UpdateIpFilterRequest>,
+) -> AppResult<Json<ApiResponse<IpFilterResponse>>> {
+    payload.validate().map_err(AppError::Validation)?;
+    state.ip_filter.set(payload.rules)?;
+    Ok(Json(ApiResponse::new(ip_filter_response(&state.ip_filter))))
+}
+
+pub async fn reset_ip_filter(State(state): State<AdminState>) -> AppResult<Json<ApiResponse<IpFilterResponse>>> {
+    state.ip_filter.reset()?;
+    Ok(Json(ApiResponse::new(ip_filter_response(&state.ip_filter))))
+}
+
+pub async fn list_captures(State(state): State<AdminState>) -> Json<ApiResponse<Vec<CaptureEntry>>> {
+    Json(ApiResponse::new(state.captures.list()))
+}
+
+pub async fn get_capture(
+    Path(id): Path<Uuid>,
+    State(state): State<AdminState>,
+) -> AppResult<Json<ApiResponse<CaptureEntry>>> {
+    let entry = state
+        .captures
+        .get(id)
+        .ok_or_else(|| AppError::NotFound(format!("Capture {} not found", id)))?;
+    Ok(Json(ApiResponse::new(entry)))
+}
+
+pub async fn export_captures_har(State(state): State<AdminState>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_DISPOSITION, "attachment; filename=\"captures.har\"")],
+        Json(state.captures.to_har()),
+    )
+}
+
+pub async fn clear_captures(State(state): State<AdminState>) -> StatusCode {
+    state.captures.clear();
+    StatusCode::NO_CONTENT
+}
+
+fn log_level_response(controller: &LogLevelController) -> LogLevelResponse {
+    LogLevelResponse {
+        directives: controller.current_directives(),
+        default_directives: controller.default_directives().to_string(),
+        revert_at: controller.revert_at().map(|at| at.to_rfc3339()),
+    }
+}
+
+fn ip_filter_response(filter: &IpFilter) -> IpFilterResponse {
+    IpFilterResponse {
+        rules: filter.rules(),
+        default_rules: filter.default_rules().to_vec(),
+    }
+}
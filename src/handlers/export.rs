@@ -0,0 +1,111 @@
+use crate::domain::errors::AppError;
+use crate::handlers::negotiation::{write_ndjson, CsvEncoder, ResponseFormat};
+use crate::AppState;
+use axum::{
+    body::{Body, Bytes},
+    http::{header, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Formats the export can be streamed in, NDJSON being the default.
+pub const EXPORT_FORMATS: [ResponseFormat; 2] = [ResponseFormat::Ndjson, ResponseFormat::Csv];
+
+/// Users fetched and encoded per chunk, which bounds the memory of an export.
+const BATCH_SIZE: usize = 100;
+
+/// Streams every user in id order as a chunked body, ending with a summary of
+/// the record count and the SHA-256 of all bytes before it.
+///
+/// If a batch fails the body is aborted, so a client that did not receive the
+/// summary knows the export is incomplete.
+pub fn stream_users(service: AppState, format: ResponseFormat) -> Response {
+    tracing::info!("Exporting users");
+    let export = Export {
+        service,
+        after: None,
+        format,
+        csv: CsvEncoder::default(),
+        hasher: Sha256::new(),
+        count: 0,
+        finished: false,
+    };
+
+    let chunks = futures_util::stream::unfold(export, |mut export| async move {
+        if export.finished {
+            return None;
+        }
+        let chunk = export.next_chunk();
+        if let Err(error) = &chunk {
+            tracing::error!(error = %error, exported = export.count, "User export aborted");
+            export.finished = true;
+        }
+        Some((chunk, export))
+    });
+
+    (
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static(format.content_type())),
+            (header::VARY, HeaderValue::from_static("accept")),
+        ],
+        Body::from_stream(chunks),
+    )
+        .into_response()
+}
+
+struct Export {
+    service: AppState,
+    /// Id of the last user exported; the next batch starts after it.
+    after: Option<Uuid>,
+    format: ResponseFormat,
+    csv: CsvEncoder,
+    hasher: Sha256,
+    count: usize,
+    finished: bool,
+}
+
+impl Export {
+    fn next_chunk(&mut self) -> Result<Bytes, AppError> {
+        // Each batch sees the store as it is now: users deleted since the export
+        // started are left out, and users created with a later id are included
+        let (users, last) = self.service.export_page(self.after, BATCH_SIZE)?;
+        if last.is_none() {
+            self.finished = true;
+            tracing::info!(exported = self.count, "User export completed");
+            return Ok(self.summary());
+        }
+        self.after = last;
+
+        let chunk = match self.format {
+            ResponseFormat::Csv => {
+                let mut chunk = Vec::new();
+                for user in &users {
+                    chunk.extend(self.csv.encode(user)?);
+                }
+                chunk
+            }
+            _ => write_ndjson(&users)?,
+        };
+
+        self.count += users.len();
+        self.hasher.update(&chunk);
+        Ok(Bytes::from(chunk))
+    }
+
+    fn summary(&mut self) -> Bytes {
+        let digest = std::mem::take(&mut self.hasher).finalize();
+        let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+        let checksum = format!("sha256:{}", hex);
+
+        let summary = match self.format {
+            // A comment line, which CSV readers can be told to skip
+            ResponseFormat::Csv => format!("# count={} checksum={}\n", self.count, checksum),
+            _ => format!(
+                "{}\n",
+                serde_json::json!({ "summary": { "count": self.count, "checksum": checksum } })
+            ),
+        };
+        Bytes::from(summary)
+    }
+}
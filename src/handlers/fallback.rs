@@ -0,0 +1,8 @@
+use crate::domain::errors::AppError;
+use axum::http::Uri;
+
+/// Answers unknown API paths with the standard JSON error, so they are never
+/// handled by a catch-all such as the static file fallback.
+pub async fn api_not_found(uri: Uri) -> AppError {
+    AppError::NotFound(format!("No route for {}", uri.path()))
+}
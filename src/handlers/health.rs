@@ -6,5 +6,5 @@ pub async fn health_check() -> Json<ApiResponse<HealthResponse>> {
 }
 
 pub async fn root() -> &'static str {
-    "🦀 Welcome to the Axum Enterprise Server! Try /api/v1/health for health check."
+    "🦀 Welcome to the Axum Enterprise Server! Try /api/v1/users to list users."
 }
\ No newline at end of file
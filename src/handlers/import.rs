@@ -0,0 +1,135 @@
+use crate::domain::{
+    errors::{AppError, AppResult, ErrorResponse},
+    requests::{CreateUserRequest, ImportUsersQuery},
+    responses::{ApiResponse, ImportJobResponse, ImportRowError},
+};
+use crate::handlers::negotiation::{self, write_csv, write_ndjson, ResponseFormat};
+use crate::openapi::CommonErrors;
+use crate::services::import::{ImportFormat, ImportService};
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Formats the error report can be downloaded in, CSV being the default.
+const REPORT_FORMATS: [ResponseFormat; 2] = [ResponseFormat::Csv, ResponseFormat::Ndjson];
+
+#[utoipa::path(
+    post,
+    path = "",
+    tag = "users",
+    params(ImportUsersQuery),
+    request_body(
+        description = "One user per CSV row, after a header row, or per NDJSON line",
+        content(
+            (String = "text/csv"),
+            (CreateUserRequest = "application/x-ndjson"),
+        ),
+    ),
+    responses(
+        (status = 200, description = "The import finished; rejected rows are listed in the error report", body = ApiResponse<ImportJobResponse>),
+        (status = 202, description = "The import runs in the background; poll the job at `Location`", body = ApiResponse<ImportJobResponse>,
+            headers(("location" = String, description = "URL of the import job"))),
+        (status = 413, description = "The upload is larger than `import.max_upload_bytes`"),
+        (status = 415, description = "`UNSUPPORTED_MEDIA_TYPE`: the upload is neither CSV nor NDJSON", body = ErrorResponse),
+        (status = 422, description = "`VALIDATION_FAILED`: the column mapping is invalid or a field has no column", body = ErrorResponse),
+        CommonErrors,
+    )
+)]
+pub async fn import_users(
+    Query(query): Query<ImportUsersQuery>,
+    State(imports): State<Arc<ImportService>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> AppResult<Response> {
+    let format = upload_format(&headers)?;
+    let job = imports.submit(format, body, query).await?;
+
+    if job.finished_at.is_some() {
+        return Ok(Json(ApiResponse::new(job)).into_response());
+    }
+    let location = job.links.self_link.clone();
+    Ok((StatusCode::ACCEPTED, [(header::LOCATION, location)], Json(ApiResponse::new(job))).into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/jobs/{id}",
+    tag = "users",
+    params(("id" = Uuid, Path, description = "Import job ID")),
+    responses(
+        (status = 200, description = "Progress of the import", body = ApiResponse<ImportJobResponse>),
+        (status = 404, description = "`NOT_FOUND`: no such job, or it finished long ago", body = ErrorResponse),
+        CommonErrors,
+    )
+)]
+pub async fn get_import_job(
+    Path(id): Path<Uuid>,
+    State(imports): State<Arc<ImportService>>,
+) -> AppResult<Json<ApiResponse<ImportJobResponse>>> {
+    Ok(Json(ApiResponse::new(imports.get(id)?)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/jobs/{id}/errors",
+    tag = "users",
+    params(("id" = Uuid, Path, description = "Import job ID")),
+    responses(
+        (status = 200, description = "One record per violated rule of each rejected row, ordered by line", content(
+            (String = "text/csv"),
+            (ImportRowError = "application/x-ndjson"),
+        )),
+        (status = 404, description = "`NOT_FOUND`: no such job, or it finished long ago", body = ErrorResponse),
+        (status = 406, description = "`NOT_ACCEPTABLE`: neither CSV nor NDJSON is accepted", body = ErrorResponse),
+        CommonErrors,
+    )
+)]
+pub async fn get_import_errors(
+    Path(id): Path<Uuid>,
+    State(imports): State<Arc<ImportService>>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let format = ResponseFormat::negotiate(&negotiation::accept(&headers), &REPORT_FORMATS)?;
+    let errors = imports.errors(id)?;
+
+    let (body, extension) = match format {
+        // Keep the header when nothing was rejected, so the report opens as a table
+        ResponseFormat::Csv if errors.is_empty() => (b"line,field,rule,message\n".to_vec(), "csv"),
+        ResponseFormat::Csv => (write_csv(&errors)?, "csv"),
+        _ => (write_ndjson(&errors)?, "ndjson"),
+    };
+    let disposition = format!("attachment; filename=\"import-{}-errors.{}\"", id, extension);
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static(format.content_type())),
+            (header::VARY, HeaderValue::from_static("accept")),
+        ],
+        [(header::CONTENT_DISPOSITION, disposition)],
+        body,
+    )
+        .into_response())
+}
+
+fn upload_format(headers: &HeaderMap) -> AppResult<ImportFormat> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let media_type = content_type.split(';').next().unwrap_or_default().trim();
+
+    if media_type.eq_ignore_ascii_case("text/csv") {
+        Ok(ImportFormat::Csv)
+    } else if media_type.eq_ignore_ascii_case("application/x-ndjson") {
+        Ok(ImportFormat::Ndjson)
+    } else {
+        Err(AppError::UnsupportedMediaType(
+            "uploads must be text/csv or application/x-ndjson".to_string(),
+        ))
+    }
+}
@@ -1,2 +1,7 @@
+pub mod admin;
+pub mod export;
+pub mod fallback;
 pub mod health;
+pub mod import;
+pub mod negotiation;
 pub mod user;
\ No newline at end of file
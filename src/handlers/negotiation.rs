@@ -0,0 +1,301 @@
+use crate::domain::{
+    errors::AppError,
+    responses::{ApiResponse, UserProfileResponse, UserResponse, UsersListResponse},
+};
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderMap, HeaderValue},
+    response::{IntoResponse, Json, Response},
+};
+use serde::Serialize;
+
+/// Representations the list and detail endpoints can be rendered in, in order
+/// of preference when the client accepts several equally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseFormat {
+    Json,
+    Csv,
+    Ndjson,
+    MessagePack,
+}
+
+impl ResponseFormat {
+    pub const ALL: [ResponseFormat; 4] = [Self::Json, Self::Csv, Self::Ndjson, Self::MessagePack];
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Ndjson => "application/x-ndjson",
+            Self::MessagePack => "application/msgpack",
+        }
+    }
+
+    fn media_types(self) -> &'static [&'static str] {
+        match self {
+            Self::Json => &["application/json"],
+            Self::Csv => &["text/csv"],
+            Self::Ndjson => &["application/x-ndjson"],
+            Self::MessagePack => &["application/msgpack", "application/x-msgpack", "application/vnd.msgpack"],
+        }
+    }
+
+    /// Picks the format in `supported` with the highest quality in `accept`. A
+    /// missing or empty header means the first supported format.
+    ///
+    /// `application/problem+json` only states how errors should be sent, so
+    /// such ranges are ignored; a header listing nothing else gets the default.
+    pub fn negotiate(accept: &str, supported: &[Self]) -> Result<Self, AppError> {
+        let ranges: Vec<(&str, f32)> = accept
+            .split(',')
+            .filter_map(|range| {
+                let mut params = range.split(';').map(str::trim);
+                let media_range = params
+                    .next()
+                    .filter(|m| !m.is_empty() && !m.eq_ignore_ascii_case("application/problem+json"))?;
+                let quality = params
+                    .find_map(|param| {
+                        let (name, value) = param.split_once('=')?;
+                        name.trim().eq_ignore_ascii_case("q").then_some(value.trim())
+                    })
+                    .and_then(|q| q.parse().ok())
+                    .unwrap_or(1.0);
+                Some((media_range, quality))
+            })
+            .collect();
+        if ranges.is_empty() {
+            return Ok(supported[0]);
+        }
+
+        let mut best: Option<(Self, f32)> = None;
+        for &format in supported {
+            let Some(quality) = format.quality(&ranges) else {
+                continue;
+            };
+            if quality > 0.0 && best.is_none_or(|(_, best_quality)| quality > best_quality) {
+                best = Some((format, quality));
+            }
+        }
+
+        best.map(|(format, _)| format).ok_or_else(|| {
+            let supported: Vec<&str> = supported.iter().map(|f| f.media_types()[0]).collect();
+            AppError::NotAcceptable(format!("supported media types are {}", supported.join(", ")))
+        })
+    }
+
+    /// Quality of the most specific range matching this format, per RFC 9110.
+    fn quality(self, ranges: &[(&str, f32)]) -> Option<f32> {
+        let media_types = self.media_types();
+        let primary_type = media_types[0].split('/').next().unwrap_or_default();
+        let specificity = |range: &str| {
+            if media_types.iter().any(|m| range.eq_ignore_ascii_case(m)) {
+                Some(2)
+            } else if range
+                .strip_suffix("/*")
+                .is_some_and(|t| t.eq_ignore_ascii_case(primary_type))
+            {
+                Some(1)
+            } else if range == "*/*" {
+                Some(0)
+            } else {
+                None
+            }
+        };
+
+        ranges
+            .iter()
+            .filter_map(|(range, quality)| specificity(range).map(|s| (s, *quality)))
+            .max_by_key(|(s, _)| *s)
+            .map(|(_, quality)| quality)
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ResponseFormat
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Self::negotiate(&accept(&parts.headers), &Self::ALL)
+    }
+}
+
+/// All `Accept` headers of a request joined into one list.
+pub fn accept(headers: &HeaderMap) -> String {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Response bodies that are a list of flat records, so they can be written as
+/// CSV rows or NDJSON lines.
+pub trait Rows {
+    type Row: Serialize;
+
+    fn rows(&self) -> &[Self::Row];
+}
+
+impl Rows for UsersListResponse {
+    type Row = UserResponse;
+
+    fn rows(&self) -> &[UserResponse] {
+        &self.users
+    }
+}
+
+impl Rows for UserResponse {
+    type Row = UserResponse;
+
+    fn rows(&self) -> &[UserResponse] {
+        std::slice::from_ref(self)
+    }
+}
+
+impl Rows for UserProfileResponse {
+    type Row = UserProfileResponse;
+
+    fn rows(&self) -> &[UserProfileResponse] {
+        std::slice::from_ref(self)
+    }
+}
+
+/// Renders `data` in the negotiated format. JSON and MessagePack keep the
+/// `ApiResponse` envelope; CSV and NDJSON contain only the records.
+pub struct Negotiated<T> {
+    pub format: ResponseFormat,
+    pub data: T,
+}
+
+impl<T> Negotiated<T> {
+    pub fn new(format: ResponseFormat, data: T) -> Self {
+        Self { format, data }
+    }
+}
+
+impl<T: Serialize + Rows> IntoResponse for Negotiated<T> {
+    fn into_response(self) -> Response {
+        let body = match self.format {
+            ResponseFormat::Json => {
+                let mut response = Json(ApiResponse::new(self.data)).into_response();
+                response
+                    .headers_mut()
+                    .append(header::VARY, HeaderValue::from_static("accept"));
+                return response;
+            }
+            ResponseFormat::MessagePack => write_msgpack(&ApiResponse::new(&self.data)),
+            ResponseFormat::Csv => write_csv(self.data.rows()),
+            ResponseFormat::Ndjson => write_ndjson(self.data.rows()),
+        };
+
+        match body {
+            Ok(body) => (
+                [
+                    (header::CONTENT_TYPE, HeaderValue::from_static(self.format.content_type())),
+                    (header::VARY, HeaderValue::from_static("accept")),
+                ],
+                body,
+            )
+                .into_response(),
+            Err(error) => error.into_response(),
+        }
+    }
+}
+
+/// Writes struct fields as map keys and IDs and timestamps as strings, as
+/// JSON does, so clients can treat both formats alike.
+fn write_msgpack<T: Serialize>(value: &T) -> Result<Vec<u8>, AppError> {
+    let mut body = Vec::new();
+    let mut serializer = rmp_serde::Serializer::new(&mut body)
+        .with_struct_map()
+        .with_human_readable();
+    value
+        .serialize(&mut serializer)
+        .map_err(|e| AppError::Internal(format!("MessagePack serialization failed: {}", e)))?;
+    Ok(body)
+}
+
+pub fn write_csv<R: Serialize>(rows: &[R]) -> Result<Vec<u8>, AppError> {
+    let mut encoder = CsvEncoder::default();
+    let mut body = Vec::new();
+    for row in rows {
+        body.extend(encoder.encode(row)?);
+    }
+    Ok(body)
+}
+
+pub fn write_ndjson<R: Serialize>(rows: &[R]) -> Result<Vec<u8>, AppError> {
+    let mut body = Vec::new();
+    for row in rows {
+        serde_json::to_writer(&mut body, row)
+            .map_err(|e| AppError::Internal(format!("NDJSON serialization failed: {}", e)))?;
+        body.push(b'\n');
+    }
+    Ok(body)
+}
+
+/// Encodes records as CSV rows, preceded by a header row taken from the field
+/// names of the first record. Embedded objects become one column per field,
+/// named like `profile.is_adult`.
+#[derive(Debug, Default)]
+pub struct CsvEncoder {
+    header_written: bool,
+}
+
+impl CsvEncoder {
+    pub fn encode<R: Serialize>(&mut self, row: &R) -> Result<Vec<u8>, AppError> {
+        let error = |e: &dyn std::fmt::Display| AppError::Internal(format!("CSV serialization failed: {}", e));
+
+        let record = match serde_json::to_value(row).map_err(|e| error(&e))? {
+            serde_json::Value::Object(record) => record,
+            _ => return Err(error(&"rows must be records")),
+        };
+        let mut fields = Vec::new();
+        flatten("", record, &mut fields);
+
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        if !self.header_written {
+            writer
+                .write_record(fields.iter().map(|(name, _)| name))
+                .map_err(|e| error(&e))?;
+            self.header_written = true;
+        }
+        writer
+            .write_record(fields.iter().map(|(_, value)| csv_cell(value)))
+            .map_err(|e| error(&e))?;
+        writer.into_inner().map_err(|e| error(&e))
+    }
+}
+
+fn flatten(prefix: &str, record: serde_json::Map<String, serde_json::Value>, fields: &mut Vec<(String, serde_json::Value)>) {
+    for (name, value) in record {
+        let name = if prefix.is_empty() { name } else { format!("{}.{}", prefix, name) };
+        match value {
+            serde_json::Value::Object(nested) => flatten(&name, nested, fields),
+            value => fields.push((name, value)),
+        }
+    }
+}
+
+fn csv_cell(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(text) => escape_formula(text),
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Spreadsheets evaluate cells starting with these characters as formulas, so a
+/// user named `=HYPERLINK(...)` could run code on an analyst's machine.
+fn escape_formula(text: &str) -> String {
+    if text.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", text)
+    } else {
+        text.to_string()
+    }
+}
@@ -1,24 +1,85 @@
 use crate::domain::{
-    errors::AppResult,
-    requests::{CreateUserRequest, ListUsersQuery, UpdateUserRequest},
+    errors::{AppResult, ErrorResponse},
+    requests::{CreateUserRequest, GetUserQuery, ListUsersQuery, UpdateUserRequest},
     responses::{ApiResponse, UserProfileResponse, UserResponse, UsersListResponse},
 };
+use crate::handlers::export::{self, EXPORT_FORMATS};
+use crate::handlers::negotiation::{self, Negotiated, ResponseFormat};
+use crate::openapi::CommonErrors;
 use crate::AppState;
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json,
+    http::{header, HeaderMap, HeaderName, StatusCode},
+    response::{Json, Response},
 };
 use uuid::Uuid;
 
+#[utoipa::path(
+    get,
+    path = "",
+    tag = "users",
+    params(ListUsersQuery),
+    responses(
+        (status = 200, description = "A page of users; CSV and NDJSON contain one record per user", content(
+            (ApiResponse<UsersListResponse> = "application/json"),
+            (ApiResponse<UsersListResponse> = "application/msgpack"),
+            (UserResponse = "application/x-ndjson"),
+            (String = "text/csv"),
+        ), headers(
+            ("link" = String, description = "RFC 8288 `first`, `prev`, `next` and `last` page links, in every format"),
+        )),
+        (status = 400, description = "`INVALID_INPUT`: `fields` or `expand` names something unknown", body = ErrorResponse),
+        (status = 406, description = "`NOT_ACCEPTABLE`: none of the accepted media types is supported", body = ErrorResponse),
+        (status = 422, description = "`VALIDATION_FAILED`: the paging parameters are out of range", body = ErrorResponse),
+        CommonErrors,
+    )
+)]
 pub async fn list_users(
     Query(query): Query<ListUsersQuery>,
     State(service): State<AppState>,
-) -> AppResult<Json<ApiResponse<UsersListResponse>>> {
+    format: ResponseFormat,
+) -> AppResult<([(HeaderName, String); 1], Negotiated<UsersListResponse>)> {
     let response = service.list_users(query)?;
-    Ok(Json(ApiResponse::new(response)))
+    // CSV and NDJSON carry only the users, so navigation also goes in a header
+    let link = response.links.to_header();
+    Ok(([(header::LINK, link)], Negotiated::new(format, response)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/export",
+    tag = "users",
+    responses(
+        (status = 200, description = "Every user, streamed in id order. The last line is a summary: \
+            `{\"summary\":{\"count\":N,\"checksum\":\"sha256:...\"}}` in NDJSON, `# count=N checksum=sha256:...` in CSV. \
+            The checksum covers all bytes before the summary; a missing summary means the export was cut short.", content(
+            (UserResponse = "application/x-ndjson"),
+            (String = "text/csv"),
+        )),
+        (status = 406, description = "`NOT_ACCEPTABLE`: neither NDJSON nor CSV is accepted", body = ErrorResponse),
+        CommonErrors,
+    )
+)]
+pub async fn export_users(
+    State(service): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let format = ResponseFormat::negotiate(&negotiation::accept(&headers), &EXPORT_FORMATS)?;
+    Ok(export::stream_users(service, format))
 }
 
+#[utoipa::path(
+    post,
+    path = "",
+    tag = "users",
+    request_body = CreateUserRequest,
+    responses(
+        (status = 201, description = "The created user", body = ApiResponse<UserResponse>),
+        (status = 422, description = "`VALIDATION_FAILED`: one or more fields are invalid", body = ErrorResponse),
+        (status = 409, description = "`USER_ALREADY_EXISTS`: the email address is taken", body = ErrorResponse),
+        CommonErrors,
+    )
+)]
 pub async fn create_user(
     State(service): State<AppState>,
     Json(payload): Json<CreateUserRequest>,
@@ -27,14 +88,49 @@ pub async fn create_user(
     Ok((StatusCode::CREATED, Json(ApiResponse::new(response))))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    tag = "users",
+    params(("id" = Uuid, Path, description = "User ID"), GetUserQuery),
+    responses(
+        (status = 200, description = "The user", content(
+            (ApiResponse<UserResponse> = "application/json"),
+            (ApiResponse<UserResponse> = "application/msgpack"),
+            (UserResponse = "application/x-ndjson"),
+            (String = "text/csv"),
+        )),
+        (status = 400, description = "`INVALID_INPUT`: `fields` or `expand` names something unknown", body = ErrorResponse),
+        (status = 406, description = "`NOT_ACCEPTABLE`: none of the accepted media types is supported", body = ErrorResponse),
+        (status = 404, description = "`USER_NOT_FOUND`", body = ErrorResponse),
+        CommonErrors,
+    )
+)]
 pub async fn get_user(
     Path(id): Path<Uuid>,
+    Query(query): Query<GetUserQuery>,
     State(service): State<AppState>,
-) -> AppResult<Json<ApiResponse<UserResponse>>> {
-    let response = service.get_user(id)?;
-    Ok(Json(ApiResponse::new(response)))
+    format: ResponseFormat,
+) -> AppResult<Negotiated<UserResponse>> {
+    let response = service.get_user(id, query)?;
+    Ok(Negotiated::new(format, response))
 }
 
+#[utoipa::path(
+    put,
+    path = "/{id}",
+    tag = "users",
+    params(("id" = Uuid, Path, description = "User ID")),
+    request_body = UpdateUserRequest,
+    responses(
+        (status = 200, description = "The updated user", body = ApiResponse<UserResponse>),
+        (status = 400, description = "`INVALID_INPUT`: no field was given", body = ErrorResponse),
+        (status = 422, description = "`VALIDATION_FAILED`: one or more fields are invalid", body = ErrorResponse),
+        (status = 404, description = "`USER_NOT_FOUND`", body = ErrorResponse),
+        (status = 409, description = "`USER_ALREADY_EXISTS`: the new email address is taken", body = ErrorResponse),
+        CommonErrors,
+    )
+)]
 pub async fn update_user(
     Path(id): Path<Uuid>,
     State(service): State<AppState>,
@@ -44,6 +140,17 @@ pub async fn update_user(
     Ok(Json(ApiResponse::new(response)))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    tag = "users",
+    params(("id" = Uuid, Path, description = "User ID")),
+    responses(
+        (status = 204, description = "The user was deleted"),
+        (status = 404, description = "`USER_NOT_FOUND`", body = ErrorResponse),
+        CommonErrors,
+    )
+)]
 pub async fn delete_user(
     Path(id): Path<Uuid>,
     State(service): State<AppState>,
@@ -52,10 +159,28 @@ pub async fn delete_user(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    get,
+    path = "/{id}/profile",
+    tag = "users",
+    params(("id" = Uuid, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "The user's profile", content(
+            (ApiResponse<UserProfileResponse> = "application/json"),
+            (ApiResponse<UserProfileResponse> = "application/msgpack"),
+            (UserProfileResponse = "application/x-ndjson"),
+            (String = "text/csv"),
+        )),
+        (status = 406, description = "`NOT_ACCEPTABLE`: none of the accepted media types is supported", body = ErrorResponse),
+        (status = 404, description = "`USER_NOT_FOUND`", body = ErrorResponse),
+        CommonErrors,
+    )
+)]
 pub async fn get_user_profile(
     Path(id): Path<Uuid>,
     State(service): State<AppState>,
-) -> AppResult<Json<ApiResponse<UserProfileResponse>>> {
+    format: ResponseFormat,
+) -> AppResult<Negotiated<UserProfileResponse>> {
     let response = service.get_user_profile(id)?;
-    Ok(Json(ApiResponse::new(response)))
+    Ok(Negotiated::new(format, response))
 }
\ No newline at end of file
@@ -2,21 +2,36 @@ mod config;
 mod domain;
 mod handlers;
 mod middleware;
+mod openapi;
 mod repositories;
 mod routes;
+mod server;
 mod services;
+mod telemetry;
 mod utils;
 
 use anyhow::Result;
 use config::AppConfig;
-use repositories::user::UserRepository;
-use services::user::UserService;
-use std::sync::Arc;
+use domain::links::ApiUrls;
+use middleware::ip_filter::IpFilter;
+use repositories::user::{LockPoisonPolicy, UserRepository};
+use services::{import::ImportService, user::UserService};
+use std::{path::Path, sync::Arc};
+use telemetry::{access_log::AccessLogger, capture::CaptureStore, log_level::LogLevelController};
 use tracing::{info, Level};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::{layer::SubscriberExt, reload, util::SubscriberInitExt};
+use utils::email::DomainBlocklist;
 
 pub type AppState = Arc<UserService>;
 
+/// State shared by the operational endpoints on the admin listener.
+#[derive(Clone)]
+pub struct AdminState {
+    pub log_level: Arc<LogLevelController>,
+    pub captures: Arc<CaptureStore>,
+    pub ip_filter: Arc<IpFilter>,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // Load environment variables
@@ -26,29 +41,77 @@ async fn main() -> Result<()> {
     let config = AppConfig::load()?;
 
     // Initialize tracing
-    init_tracing(&config)?;
+    telemetry::redaction::init(&config.logging.redaction)?;
+    let log_level = Arc::new(init_tracing(&config)?);
+    middleware::panic::install_panic_hook();
 
     // Initialize dependencies
-    let user_repository = Arc::new(UserRepository::new());
-    let user_service = Arc::new(UserService::new(user_repository));
+    let lock_poison_policy: LockPoisonPolicy = config
+        .repository
+        .lock_poison_policy
+        .parse()
+        .map_err(anyhow::Error::msg)?;
+    let user_repository = Arc::new(UserRepository::with_lock_poison_policy(lock_poison_policy));
+    // Disposable email domains rejected when users are created or updated
+    let email_blocklist = if config.email.disposable_domains_path.is_empty() {
+        DomainBlocklist::default()
+    } else {
+        let blocklist = DomainBlocklist::load(Path::new(&config.email.disposable_domains_path))?;
+        info!(domains = blocklist.len(), "Loaded disposable email domain blocklist");
+        blocklist
+    };
+    // Absolute URLs for links in responses
+    let urls = ApiUrls::from_config(&config)?;
+    let user_service = Arc::new(UserService::new(user_repository, email_blocklist, urls.clone()));
+    let import_service = Arc::new(ImportService::new(user_service.clone(), config.import.clone(), urls));
+
+    // Open the access log, which is written independently of the application log
+    let access_logger = if config.access_log.enabled {
+        Some(AccessLogger::new(&config.access_log)?)
+    } else {
+        None
+    };
 
-    // Build the application
-    let app = routes::create_routes(user_service);
+    // Request/response capture buffer, inspected through the admin listener
+    let captures = Arc::new(CaptureStore::new(config.capture.clone()));
 
-    // Create listener
-    let listener = tokio::net::TcpListener::bind(&config.server.address).await?;
+    // Per-route client address rules, replaceable through the admin listener
+    let ip_filter = Arc::new(IpFilter::new(config.ip_filter.rules.clone())?);
 
-    info!("🚀 Server starting on {}", config.server.address);
+    // Build the application
+    let app = routes::create_routes(user_service, import_service, access_logger, captures.clone(), ip_filter.clone(), &config)?;
+    let admin_app = routes::create_admin_routes(AdminState {
+        log_level,
+        captures,
+        ip_filter,
+    });
+
+    // Create listeners
+    let (listener, admin_listener) = server::listener::bind(&config.server).await?;
+
+    // Load TLS certificates if enabled
+    let tls = if config.tls.enabled {
+        Some(server::tls::acceptor(&config.tls)?)
+    } else {
+        None
+    };
+
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    info!("🚀 Server starting on {}://{}", scheme, listener);
+    info!("🛠️ Admin endpoints on http://{}", admin_listener);
     info!("📚 Environment: {}", config.environment);
     info!("🔍 Log level: {}", config.logging.level);
 
-    // Start the server
-    axum::serve(listener, app).await?;
+    // Start the servers
+    tokio::try_join!(
+        server::serve(listener, app, tls, &config.limits),
+        server::serve(admin_listener, admin_app, None, &config.limits),
+    )?;
 
     Ok(())
 }
 
-fn init_tracing(config: &AppConfig) -> Result<()> {
+fn init_tracing(config: &AppConfig) -> Result<LogLevelController> {
     let level = match config.logging.level.as_str() {
         "trace" => Level::TRACE,
         "debug" => Level::DEBUG,
@@ -61,6 +124,10 @@ fn init_tracing(config: &AppConfig) -> Result<()> {
     let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| format!("{}={}", env!("CARGO_PKG_NAME").replace('-', "_"), level).into());
 
+    // Keep the startup directives so runtime changes can be reverted
+    let default_directives = env_filter.to_string();
+    let (env_filter, reload_handle) = reload::Layer::new(env_filter);
+
     if config.logging.json_format {
         // JSON formatted logs with spans as fields (dict/object format)
         tracing_subscriber::registry()
@@ -90,8 +157,9 @@ fn init_tracing(config: &AppConfig) -> Result<()> {
     tracing::info!(
         format = if config.logging.json_format { "json" } else { "text" },
         level = %config.logging.level,
+        redaction = %config.logging.redaction.mode,
         "Logging initialized"
     );
 
-    Ok(())
+    Ok(LogLevelController::new(reload_handle, default_directives))
 }
\ No newline at end of file
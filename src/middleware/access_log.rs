@@ -0,0 +1,112 @@
+use crate::middleware::client_info::ClientInfo;
+use crate::telemetry::{
+    access_log::{AccessLogEntry, AccessLogger},
+    redaction,
+};
+use axum::{
+    body::HttpBody,
+    extract::Request,
+    http::{header, HeaderMap, HeaderName},
+    response::Response,
+};
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+
+/// Writes one access log line per request when an access logger is configured.
+#[derive(Clone)]
+pub struct AccessLogLayer {
+    logger: Option<AccessLogger>,
+}
+
+impl AccessLogLayer {
+    pub fn new(logger: Option<AccessLogger>) -> Self {
+        Self { logger }
+    }
+}
+
+impl<S> Layer<S> for AccessLogLayer {
+    type Service = AccessLogService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AccessLogService {
+            inner,
+            logger: self.logger.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AccessLogService<S> {
+    inner: S,
+    logger: Option<AccessLogger>,
+}
+
+impl<S> Service<Request> for AccessLogService<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let mut inner = self.inner.clone();
+        let Some(logger) = self.logger.clone() else {
+            return Box::pin(async move { inner.call(req).await });
+        };
+
+        let timestamp = chrono::Utc::now();
+        let remote_addr = req
+            .extensions()
+            .get::<ClientInfo>()
+            .and_then(|client| client.ip)
+            .map(|ip| ip.to_string());
+        let method = req.method().to_string();
+        let target = redaction::request_target(req.uri());
+        let protocol = format!("{:?}", req.version());
+        let referer = header_value(req.headers(), header::REFERER);
+        let user_agent = header_value(req.headers(), header::USER_AGENT);
+        let correlation_id = header_value(req.headers(), HeaderName::from_static("x-correlation-id"));
+
+        Box::pin(async move {
+            let start_time = std::time::Instant::now();
+            let result = inner.call(req).await;
+
+            if let Ok(response) = &result {
+                logger.log(&AccessLogEntry {
+                    timestamp,
+                    remote_addr,
+                    method,
+                    target,
+                    protocol,
+                    status: response.status().as_u16(),
+                    bytes: header_value(response.headers(), header::CONTENT_LENGTH)
+                        .and_then(|len| len.parse().ok())
+                        .or_else(|| response.body().size_hint().exact()),
+                    duration_ms: start_time.elapsed().as_millis(),
+                    referer,
+                    user_agent,
+                    correlation_id,
+                });
+            }
+
+            result
+        })
+    }
+}
+
+fn header_value(headers: &HeaderMap, name: HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
@@ -0,0 +1,23 @@
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    extract::Request,
+    response::Response,
+};
+
+/// Some tower-http layers replace the body type; these convert it back so the
+/// layers around them keep working with axum's `Request` and `Response`.
+pub fn into_axum_response<B>(response: axum::http::Response<B>) -> Response
+where
+    B: HttpBody<Data = Bytes> + Send + 'static,
+    B::Error: Into<axum::BoxError>,
+{
+    response.map(Body::new)
+}
+
+pub fn into_axum_request<B>(request: axum::http::Request<B>) -> Request
+where
+    B: HttpBody<Data = Bytes> + Send + 'static,
+    B::Error: Into<axum::BoxError>,
+{
+    request.map(Body::new)
+}
@@ -0,0 +1,239 @@
+use crate::middleware::client_info::ClientInfo;
+use crate::telemetry::capture::{BodyRecorder, CaptureEntry, CaptureStore, CapturedHeader, SharedRecorder};
+use crate::telemetry::redaction;
+use crate::utils::sync::lock_recover;
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    extract::Request,
+    http::{header, HeaderMap},
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use http_body::Frame;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll},
+    time::Instant,
+};
+use tower::{Layer, Service};
+use uuid::Uuid;
+
+/// Records sampled requests and responses, including their bodies, into a `CaptureStore`.
+#[derive(Clone)]
+pub struct CaptureLayer {
+    store: Arc<CaptureStore>,
+}
+
+impl CaptureLayer {
+    pub fn new(store: Arc<CaptureStore>) -> Self {
+        Self { store }
+    }
+}
+
+impl<S> Layer<S> for CaptureLayer {
+    type Service = CaptureService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CaptureService {
+            inner,
+            store: self.store.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct CaptureService<S> {
+    inner: S,
+    store: Arc<CaptureStore>,
+}
+
+impl<S> Service<Request> for CaptureService<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let mut inner = self.inner.clone();
+        if !self.store.should_capture(req.method(), req.uri().path()) {
+            return Box::pin(async move { inner.call(req).await });
+        }
+
+        // Set by the outer ClientInfoLayer; the Host header is the fallback
+        let (scheme, host) = match req.extensions().get::<ClientInfo>() {
+            Some(client) => (client.scheme.clone(), client.host.clone()),
+            None => (
+                "http".to_string(),
+                req.headers()
+                    .get(header::HOST)
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string),
+            ),
+        };
+
+        let pending = Arc::new(PendingCapture {
+            store: self.store.clone(),
+            started_at: Utc::now(),
+            start: Instant::now(),
+            correlation_id: req
+                .headers()
+                .get("x-correlation-id")
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string),
+            method: req.method().to_string(),
+            scheme,
+            host,
+            uri: redaction::uri(req.uri()),
+            version: format!("{:?}", req.version()),
+            request_headers: self.store.headers(req.headers()),
+            request_content_type: content_type(req.headers()),
+            request_body: SharedRecorder::default(),
+            response: Mutex::new(None),
+            response_body: SharedRecorder::default(),
+        });
+
+        let limit = self.store.max_body_bytes();
+        let req = req.map(|body| {
+            Body::new(RecordingBody {
+                inner: body,
+                recorder: pending.request_body.clone(),
+                limit,
+                _pending: pending.clone(),
+            })
+        });
+
+        Box::pin(async move {
+            let response = inner.call(req).await?;
+
+            *lock_recover(&pending.response, "Capture response") = Some(ResponseMeta {
+                status: response.status().as_u16(),
+                headers: pending.store.headers(response.headers()),
+                content_type: content_type(response.headers()),
+                duration_ms: pending.start.elapsed().as_millis(),
+            });
+
+            // The entry is stored once the response body has been sent and dropped
+            let recorder = pending.response_body.clone();
+            Ok(response.map(|body| {
+                Body::new(RecordingBody {
+                    inner: body,
+                    recorder,
+                    limit,
+                    _pending: pending,
+                })
+            }))
+        })
+    }
+}
+
+fn content_type(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+struct ResponseMeta {
+    status: u16,
+    headers: Vec<CapturedHeader>,
+    content_type: Option<String>,
+    duration_ms: u128,
+}
+
+/// An exchange in flight. Shared by the request and response bodies; the last
+/// one to be dropped turns it into a `CaptureEntry`.
+struct PendingCapture {
+    store: Arc<CaptureStore>,
+    started_at: DateTime<Utc>,
+    start: Instant,
+    correlation_id: Option<String>,
+    method: String,
+    scheme: String,
+    host: Option<String>,
+    uri: String,
+    version: String,
+    request_headers: Vec<CapturedHeader>,
+    request_content_type: Option<String>,
+    request_body: SharedRecorder,
+    response: Mutex<Option<ResponseMeta>>,
+    response_body: SharedRecorder,
+}
+
+impl Drop for PendingCapture {
+    fn drop(&mut self) {
+        // Panicking in drop would abort the process, so poisoned locks are recovered
+        let response = lock_recover(&self.response, "Capture response").take();
+        let request_body = body_record(&self.store, &self.request_body, self.request_content_type.clone());
+        let (status, response_headers, response_content_type, duration_ms) = match response {
+            Some(meta) => (Some(meta.status), meta.headers, meta.content_type, meta.duration_ms),
+            None => (None, Vec::new(), None, self.start.elapsed().as_millis()),
+        };
+        let response_body = body_record(&self.store, &self.response_body, response_content_type);
+
+        self.store.push(CaptureEntry {
+            id: Uuid::new_v4(),
+            started_at: self.started_at,
+            duration_ms,
+            correlation_id: self.correlation_id.take(),
+            method: std::mem::take(&mut self.method),
+            scheme: std::mem::take(&mut self.scheme),
+            host: self.host.take(),
+            uri: std::mem::take(&mut self.uri),
+            version: std::mem::take(&mut self.version),
+            request_headers: std::mem::take(&mut self.request_headers),
+            request_body,
+            status,
+            response_headers,
+            response_body,
+        });
+    }
+}
+
+fn body_record(
+    store: &CaptureStore,
+    recorder: &Mutex<BodyRecorder>,
+    content_type: Option<String>,
+) -> crate::telemetry::capture::CapturedBody {
+    let recorder = lock_recover(recorder, "Capture body");
+    store.body(recorder.bytes(), recorder.size(), content_type)
+}
+
+/// Passes a body through unchanged while copying up to `limit` bytes into a recorder.
+struct RecordingBody {
+    inner: Body,
+    recorder: SharedRecorder,
+    limit: usize,
+    _pending: Arc<PendingCapture>,
+}
+
+impl HttpBody for RecordingBody {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Bytes>, Self::Error>>> {
+        let frame = ready!(Pin::new(&mut self.inner).poll_frame(cx));
+        if let Some(Ok(frame)) = &frame {
+            if let Some(data) = frame.data_ref() {
+                lock_recover(&self.recorder, "Capture body").record(data, self.limit);
+            }
+        }
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
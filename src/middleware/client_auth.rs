@@ -0,0 +1,91 @@
+use crate::config::ClientAllowRule;
+use crate::domain::errors::AppError;
+use crate::server::tls::ClientIdentity;
+use crate::utils::network::path_has_prefix;
+use axum::{
+    extract::Request,
+    response::{IntoResponse, Response},
+};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+
+/// Restricts routes to the client certificate identities listed in the allowlist.
+#[derive(Clone)]
+pub struct ClientAllowlistLayer {
+    rules: Arc<Vec<ClientAllowRule>>,
+}
+
+impl ClientAllowlistLayer {
+    pub fn new(rules: Vec<ClientAllowRule>) -> Self {
+        Self { rules: Arc::new(rules) }
+    }
+}
+
+impl<S> Layer<S> for ClientAllowlistLayer {
+    type Service = ClientAllowlistService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ClientAllowlistService {
+            inner,
+            rules: self.rules.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ClientAllowlistService<S> {
+    inner: S,
+    rules: Arc<Vec<ClientAllowRule>>,
+}
+
+impl<S> ClientAllowlistService<S> {
+    fn matching_rule(&self, req: &Request) -> Option<&ClientAllowRule> {
+        let path = req.uri().path();
+        let method = req.method().as_str();
+
+        self.rules.iter().find(|rule| {
+            path_has_prefix(path, &rule.path_prefix)
+                && (rule.methods.is_empty() || rule.methods.iter().any(|m| m.eq_ignore_ascii_case(method)))
+        })
+    }
+}
+
+impl<S> Service<Request> for ClientAllowlistService<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        if let Some(rule) = self.matching_rule(&req) {
+            let identity = req.extensions().get::<ClientIdentity>();
+            let allowed = identity.is_some_and(|id| rule.identities.iter().any(|allowed| id.matches(allowed)));
+
+            if !allowed {
+                tracing::warn!(
+                    client_identity = identity.map(|id| id.to_string()).unwrap_or_else(|| "anonymous".to_string()),
+                    path_prefix = %rule.path_prefix,
+                    "Client identity not allowed for route"
+                );
+                let response = AppError::Forbidden("Client identity is not allowed to call this endpoint".to_string())
+                    .into_response();
+                return Box::pin(async move { Ok(response) });
+            }
+        }
+
+        let mut inner = self.inner.clone();
+        Box::pin(async move { inner.call(req).await })
+    }
+}
@@ -0,0 +1,432 @@
+use crate::config::ProxyConfig;
+use crate::domain::errors::AppError;
+use crate::server::TlsConnection;
+use crate::utils::network::parse_network;
+use anyhow::{Context as _, Result};
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts, Request},
+    http::{header, request::Parts, HeaderMap},
+    response::Response,
+};
+use ipnet::IpNet;
+use std::{
+    fmt,
+    future::Future,
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+
+/// The client as seen by the edge of our infrastructure, resolved from
+/// forwarding headers when the request came through a trusted proxy.
+#[derive(Debug, Clone)]
+pub struct ClientInfo {
+    /// `None` when the client address is unknown or obfuscated by a proxy.
+    /// IPv4-mapped IPv6 addresses (`::ffff:192.0.2.1`) are stored as IPv4.
+    pub ip: Option<IpAddr>,
+    pub scheme: String,
+    pub host: Option<String>,
+}
+
+impl fmt::Display for ClientInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.ip {
+            Some(ip) => write!(f, "{}", ip),
+            None => write!(f, "unknown"),
+        }
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientInfo
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<ClientInfo>()
+            .cloned()
+            .ok_or_else(|| AppError::Internal("ClientInfoLayer is not installed".to_string()))
+    }
+}
+
+/// Which header trusted proxies report the client in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ForwardedHeader {
+    XForwardedFor,
+    Forwarded,
+}
+
+#[derive(Debug)]
+struct ProxyTrust {
+    trusted_proxies: Vec<IpNet>,
+    header: ForwardedHeader,
+    trust_unix_socket: bool,
+}
+
+/// Resolves the `ClientInfo` of each request and stores it as a request extension.
+#[derive(Clone)]
+pub struct ClientInfoLayer {
+    trust: Arc<ProxyTrust>,
+}
+
+impl ClientInfoLayer {
+    pub fn new(config: &ProxyConfig) -> Result<Self> {
+        let trusted_proxies = config
+            .trusted_proxies
+            .iter()
+            .map(|entry| parse_network(entry).map_err(anyhow::Error::msg).context("Invalid trusted proxy"))
+            .collect::<Result<_>>()?;
+        let header = match config.forwarded_header.to_ascii_lowercase().as_str() {
+            "x-forwarded-for" => ForwardedHeader::XForwardedFor,
+            "forwarded" => ForwardedHeader::Forwarded,
+            other => anyhow::bail!("Unknown forwarded header: {} (expected x-forwarded-for or forwarded)", other),
+        };
+
+        Ok(Self {
+            trust: Arc::new(ProxyTrust {
+                trusted_proxies,
+                header,
+                trust_unix_socket: config.trust_unix_socket,
+            }),
+        })
+    }
+}
+
+impl<S> Layer<S> for ClientInfoLayer {
+    type Service = ClientInfoService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ClientInfoService {
+            inner,
+            trust: self.trust.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ClientInfoService<S> {
+    inner: S,
+    trust: Arc<ProxyTrust>,
+}
+
+impl<S> Service<Request> for ClientInfoService<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request) -> Self::Future {
+        let client = resolve(&req, &self.trust);
+        req.extensions_mut().insert(client);
+
+        let mut inner = self.inner.clone();
+        Box::pin(async move { inner.call(req).await })
+    }
+}
+
+fn resolve(req: &Request, trust: &ProxyTrust) -> ClientInfo {
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_canonical());
+    let direct = ClientInfo {
+        ip: peer,
+        scheme: if req.extensions().get::<TlsConnection>().is_some() {
+            "https".to_string()
+        } else {
+            "http".to_string()
+        },
+        host: req
+            .headers()
+            .get(header::HOST)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+            .or_else(|| req.uri().authority().map(|a| a.to_string())),
+    };
+
+    let is_trusted = |ip: &IpAddr| trust.trusted_proxies.iter().any(|net| net.contains(ip));
+    // Unix socket peers have no address to check
+    let peer_trusted = match peer {
+        Some(ip) => is_trusted(&ip),
+        None => trust.trust_unix_socket,
+    };
+    if !peer_trusted {
+        return direct;
+    }
+
+    // Only the configured header is read: a proxy that sets one of them passes
+    // the other through from the client unchanged
+    let forwarded = match trust.header {
+        ForwardedHeader::Forwarded => from_forwarded(req.headers(), &is_trusted),
+        ForwardedHeader::XForwardedFor => from_x_forwarded(req.headers(), &is_trusted),
+    };
+    forwarded
+        .map(|forwarded| ClientInfo {
+            ip: forwarded.ip,
+            scheme: forwarded.scheme.unwrap_or(direct.scheme.clone()),
+            host: forwarded.host.or(direct.host.clone()),
+        })
+        .unwrap_or(direct)
+}
+
+struct Forwarded {
+    ip: Option<IpAddr>,
+    scheme: Option<String>,
+    host: Option<String>,
+}
+
+/// Parses RFC 7239 `Forwarded` headers. Hops are walked from the nearest proxy
+/// outwards until one that is not trusted, which is the client; its element
+/// also carries the scheme and host the client used.
+fn from_forwarded(headers: &HeaderMap, is_trusted: &dyn Fn(&IpAddr) -> bool) -> Option<Forwarded> {
+    let elements: Vec<Vec<(String, String)>> = headers
+        .get_all(header::FORWARDED)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|element| {
+            element
+                .split(';')
+                .filter_map(|pair| pair.split_once('='))
+                .map(|(key, value)| (key.trim().to_ascii_lowercase(), value.trim().trim_matches('"').to_string()))
+                .collect()
+        })
+        .collect();
+
+    let param = |element: &[(String, String)], name: &str| {
+        element.iter().find(|(key, _)| key == name).map(|(_, value)| value.clone())
+    };
+
+    let mut client = None;
+    for element in elements.iter().rev() {
+        let ip = param(element, "for").and_then(|value| parse_node(&value));
+        client = Some(Forwarded {
+            ip,
+            scheme: param(element, "proto").map(|p| p.to_ascii_lowercase()),
+            host: param(element, "host"),
+        });
+        if !ip.is_some_and(|ip| is_trusted(&ip)) {
+            break;
+        }
+    }
+    client
+}
+
+/// Parses `X-Forwarded-For`, `X-Forwarded-Proto` and `X-Forwarded-Host`.
+/// Proto and host are taken from the nearest proxy, as they carry no per-hop
+/// information.
+fn from_x_forwarded(headers: &HeaderMap, is_trusted: &dyn Fn(&IpAddr) -> bool) -> Option<Forwarded> {
+    let list = |name: &str| -> Vec<String> {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .collect()
+    };
+
+    let hops = list("x-forwarded-for");
+    if hops.is_empty() {
+        return None;
+    }
+
+    let mut ip = None;
+    for hop in hops.iter().rev() {
+        ip = parse_node(hop);
+        if !ip.is_some_and(|ip| is_trusted(&ip)) {
+            break;
+        }
+    }
+
+    Some(Forwarded {
+        ip,
+        scheme: list("x-forwarded-proto").pop().map(|p| p.to_ascii_lowercase()),
+        host: list("x-forwarded-host").pop(),
+    })
+}
+
+/// Parses a node such as `192.0.2.1`, `192.0.2.1:4711` or `[2001:db8::1]:4711`.
+/// Obfuscated identifiers (`unknown`, `_hidden`) yield `None`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let ip = match node.strip_prefix('[') {
+        Some(rest) => rest.split_once(']').and_then(|(ip, _)| ip.parse().ok()),
+        None => node
+            .parse()
+            .ok()
+            .or_else(|| node.parse::<SocketAddr>().ok().map(|addr| addr.ip())),
+    };
+    ip.map(|ip: IpAddr| ip.to_canonical())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+
+    fn trust(header: &str, trusted_proxies: &[&str]) -> Arc<ProxyTrust> {
+        let config = ProxyConfig {
+            trusted_proxies: trusted_proxies.iter().map(|p| p.to_string()).collect(),
+            forwarded_header: header.to_string(),
+            trust_unix_socket: false,
+        };
+        ClientInfoLayer::new(&config).unwrap().trust
+    }
+
+    fn request(peer: Option<&str>, headers: &[(&str, &str)]) -> Request {
+        let mut builder = Request::builder().uri("/").header(header::HOST, "origin.internal");
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        let mut req = builder.body(Body::empty()).unwrap();
+        if let Some(peer) = peer {
+            req.extensions_mut().insert(ConnectInfo(peer.parse::<SocketAddr>().unwrap()));
+        }
+        req
+    }
+
+    fn ip(value: &str) -> Option<IpAddr> {
+        Some(value.parse().unwrap())
+    }
+
+    #[test]
+    fn ignores_forwarding_headers_from_untrusted_peers() {
+        let req = request(
+            Some("203.0.113.5:50000"),
+            &[("x-forwarded-for", "198.51.100.7"), ("x-forwarded-host", "evil.example")],
+        );
+
+        let client = resolve(&req, &trust("x-forwarded-for", &["10.0.0.0/8"]));
+
+        assert_eq!(client.ip, ip("203.0.113.5"));
+        assert_eq!(client.scheme, "http");
+        assert_eq!(client.host.as_deref(), Some("origin.internal"));
+    }
+
+    #[test]
+    fn walks_x_forwarded_for_to_the_first_untrusted_hop() {
+        let req = request(
+            Some("10.0.0.1:50000"),
+            &[
+                ("x-forwarded-for", "192.0.2.66, 198.51.100.7, 10.0.0.2"),
+                ("x-forwarded-proto", "HTTPS"),
+                ("x-forwarded-host", "api.example.com"),
+            ],
+        );
+
+        let client = resolve(&req, &trust("x-forwarded-for", &["10.0.0.0/8"]));
+
+        // 192.0.2.66 was added by the client itself and is not believed
+        assert_eq!(client.ip, ip("198.51.100.7"));
+        assert_eq!(client.scheme, "https");
+        assert_eq!(client.host.as_deref(), Some("api.example.com"));
+    }
+
+    #[test]
+    fn parses_forwarded_ipv6_nodes_in_brackets_with_ports() {
+        let req = request(
+            Some("[2001:db8:ffff::1]:443"),
+            &[(
+                "forwarded",
+                "for=\"[2001:db8::7]:4711\";proto=https;host=api.example.com, for=\"[2001:db8:ffff::2]\"",
+            )],
+        );
+
+        let client = resolve(&req, &trust("forwarded", &["2001:db8:ffff::/48"]));
+
+        assert_eq!(client.ip, ip("2001:db8::7"));
+        assert_eq!(client.scheme, "https");
+        assert_eq!(client.host.as_deref(), Some("api.example.com"));
+    }
+
+    #[test]
+    fn parses_nodes_with_and_without_ports() {
+        assert_eq!(parse_node("192.0.2.1"), ip("192.0.2.1"));
+        assert_eq!(parse_node("192.0.2.1:4711"), ip("192.0.2.1"));
+        assert_eq!(parse_node("2001:db8::1"), ip("2001:db8::1"));
+        assert_eq!(parse_node("[2001:db8::1]"), ip("2001:db8::1"));
+        assert_eq!(parse_node("[2001:db8::1]:4711"), ip("2001:db8::1"));
+        assert_eq!(parse_node("::ffff:192.0.2.1"), ip("192.0.2.1"));
+    }
+
+    #[test]
+    fn obfuscated_and_unknown_nodes_have_no_address() {
+        assert_eq!(parse_node("unknown"), None);
+        assert_eq!(parse_node("_hidden"), None);
+        assert_eq!(parse_node("[_hidden]"), None);
+
+        let req = request(Some("10.0.0.1:50000"), &[("forwarded", "for=unknown;proto=https")]);
+        let client = resolve(&req, &trust("forwarded", &["10.0.0.0/8"]));
+
+        // The client is hidden, not the proxy, so the peer address is not used
+        assert_eq!(client.ip, None);
+        assert_eq!(client.scheme, "https");
+    }
+
+    #[test]
+    fn stops_at_an_obfuscated_hop() {
+        let req = request(Some("10.0.0.1:50000"), &[("x-forwarded-for", "198.51.100.7, _hidden")]);
+
+        let client = resolve(&req, &trust("x-forwarded-for", &["10.0.0.0/8"]));
+
+        assert_eq!(client.ip, None);
+    }
+
+    #[test]
+    fn ignores_x_forwarded_for_when_forwarded_is_configured() {
+        let headers = [
+            ("x-forwarded-for", "192.0.2.66"),
+            ("forwarded", "for=198.51.100.7;proto=https"),
+        ];
+        let req = request(Some("10.0.0.1:50000"), &headers);
+
+        let client = resolve(&req, &trust("forwarded", &["10.0.0.0/8"]));
+        assert_eq!(client.ip, ip("198.51.100.7"));
+
+        // Without a Forwarded header the peer is the client; X-Forwarded-For
+        // may have been passed through from anyone
+        let req = request(Some("10.0.0.1:50000"), &headers[..1]);
+        let client = resolve(&req, &trust("forwarded", &["10.0.0.0/8"]));
+        assert_eq!(client.ip, ip("10.0.0.1"));
+    }
+
+    #[test]
+    fn ignores_forwarded_when_x_forwarded_for_is_configured() {
+        let req = request(Some("10.0.0.1:50000"), &[("forwarded", "for=192.0.2.66;proto=https")]);
+
+        let client = resolve(&req, &trust("x-forwarded-for", &["10.0.0.0/8"]));
+
+        assert_eq!(client.ip, ip("10.0.0.1"));
+        assert_eq!(client.scheme, "http");
+    }
+
+    #[test]
+    fn trusts_unix_socket_peers_only_when_configured() {
+        let req = request(None, &[("x-forwarded-for", "198.51.100.7")]);
+
+        assert_eq!(resolve(&req, &trust("x-forwarded-for", &[])).ip, None);
+
+        let config = ProxyConfig {
+            trusted_proxies: Vec::new(),
+            forwarded_header: "x-forwarded-for".to_string(),
+            trust_unix_socket: true,
+        };
+        let trust = ClientInfoLayer::new(&config).unwrap().trust;
+        assert_eq!(resolve(&req, &trust).ip, ip("198.51.100.7"));
+    }
+}
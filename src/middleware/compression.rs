@@ -0,0 +1,82 @@
+use crate::config::CompressionConfig;
+use anyhow::{bail, Result};
+use axum::http::{header, HeaderValue, Response, StatusCode};
+use http_body::Body;
+use std::sync::Arc;
+use tower_http::{
+    compression::{
+        predicate::{And, SizeAbove},
+        CompressionLayer, Predicate,
+    },
+    decompression::RequestDecompressionLayer,
+};
+
+const ALGORITHMS: &[&str] = &["gzip", "br", "zstd"];
+
+pub type ResponseCompressionLayer = CompressionLayer<And<SizeAbove, ContentTypeAllowlist>>;
+
+/// Compresses responses with whichever enabled algorithm the client prefers.
+/// With compression disabled every algorithm is off and responses pass through.
+pub fn compression_layer(config: &CompressionConfig) -> Result<ResponseCompressionLayer> {
+    validate_algorithms(&config.algorithms)?;
+    let enabled = |algorithm: &str| config.enabled && config.algorithms.iter().any(|a| a == algorithm);
+
+    let predicate = SizeAbove::new(config.min_size_bytes).and(ContentTypeAllowlist(Arc::new(config.content_types.clone())));
+
+    Ok(CompressionLayer::new()
+        .gzip(enabled("gzip"))
+        .br(enabled("br"))
+        .zstd(enabled("zstd"))
+        .compress_when(predicate))
+}
+
+/// Decodes `Content-Encoding`'d request bodies. Unsupported encodings are
+/// rejected with 415; when disabled, bodies are passed to handlers untouched.
+pub fn decompression_layer(config: &CompressionConfig) -> RequestDecompressionLayer {
+    let enabled = config.request_decompression;
+    RequestDecompressionLayer::new()
+        .gzip(enabled)
+        .br(enabled)
+        .zstd(enabled)
+        .pass_through_unaccepted(!enabled)
+}
+
+/// The compression body hides the size of responses that are sent as-is, so
+/// hyper would fall back to chunked encoding. Records the size as a header while
+/// it is still known; it is removed again when the response does get compressed.
+pub fn with_content_length<B: Body>(mut response: Response<B>) -> Response<B> {
+    let status = response.status();
+    if status.is_informational() || status == StatusCode::NO_CONTENT || status == StatusCode::NOT_MODIFIED {
+        return response;
+    }
+    if !response.headers().contains_key(header::CONTENT_LENGTH) {
+        if let Some(length) = response.body().size_hint().exact() {
+            response.headers_mut().insert(header::CONTENT_LENGTH, HeaderValue::from(length));
+        }
+    }
+    response
+}
+
+fn validate_algorithms(algorithms: &[String]) -> Result<()> {
+    if let Some(unknown) = algorithms.iter().find(|a| !ALGORITHMS.contains(&a.as_str())) {
+        bail!("Unsupported compression algorithm: {}", unknown);
+    }
+    Ok(())
+}
+
+/// Only compresses responses whose content type starts with one of the configured prefixes.
+#[derive(Debug, Clone)]
+pub struct ContentTypeAllowlist(Arc<Vec<String>>);
+
+impl Predicate for ContentTypeAllowlist {
+    fn should_compress<B>(&self, response: &Response<B>) -> bool
+    where
+        B: Body,
+    {
+        response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|content_type| self.0.iter().any(|allowed| content_type.starts_with(allowed.as_str())))
+    }
+}
@@ -0,0 +1,167 @@
+use crate::config::IpFilterRule;
+use crate::domain::errors::{AppError, AppResult};
+use crate::middleware::client_info::ClientInfo;
+use crate::utils::network::{parse_network, path_has_prefix};
+use axum::{
+    extract::Request,
+    http::Method,
+    response::{IntoResponse, Response},
+};
+use ipnet::IpNet;
+use std::{
+    future::Future,
+    net::IpAddr,
+    pin::Pin,
+    sync::{Arc, RwLock},
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+
+/// The active allow/deny rules. Replaceable at runtime through the admin
+/// listener, falling back to the configured rules on reset.
+#[derive(Debug)]
+pub struct IpFilter {
+    default_rules: Vec<IpFilterRule>,
+    active: RwLock<Arc<Vec<CompiledRule>>>,
+}
+
+#[derive(Debug)]
+struct CompiledRule {
+    rule: IpFilterRule,
+    allow: Vec<IpNet>,
+    deny: Vec<IpNet>,
+}
+
+impl CompiledRule {
+    fn compile(rule: IpFilterRule) -> Result<Self, String> {
+        let parse = |entries: &[String]| entries.iter().map(|e| parse_network(e)).collect::<Result<Vec<_>, _>>();
+        Ok(Self {
+            allow: parse(&rule.allow)?,
+            deny: parse(&rule.deny)?,
+            rule,
+        })
+    }
+
+    fn matches(&self, method: &Method, path: &str) -> bool {
+        path_has_prefix(path, &self.rule.path_prefix)
+            && (self.rule.methods.is_empty()
+                || self.rule.methods.iter().any(|m| m.eq_ignore_ascii_case(method.as_str())))
+    }
+
+    /// Deny ranges win over allow ranges; an unknown address only passes rules without an allowlist.
+    fn permits(&self, ip: Option<IpAddr>) -> bool {
+        match ip {
+            Some(ip) => {
+                !self.deny.iter().any(|net| net.contains(&ip))
+                    && (self.allow.is_empty() || self.allow.iter().any(|net| net.contains(&ip)))
+            }
+            None => self.allow.is_empty(),
+        }
+    }
+}
+
+fn compile(rules: Vec<IpFilterRule>) -> Result<Vec<CompiledRule>, String> {
+    rules.into_iter().map(CompiledRule::compile).collect()
+}
+
+impl IpFilter {
+    pub fn new(rules: Vec<IpFilterRule>) -> anyhow::Result<Self> {
+        let compiled = compile(rules.clone()).map_err(|e| anyhow::anyhow!("Invalid IP filter rule: {}", e))?;
+        Ok(Self {
+            default_rules: rules,
+            active: RwLock::new(Arc::new(compiled)),
+        })
+    }
+
+    pub fn rules(&self) -> Vec<IpFilterRule> {
+        self.active.read().unwrap().iter().map(|c| c.rule.clone()).collect()
+    }
+
+    pub fn default_rules(&self) -> &[IpFilterRule] {
+        &self.default_rules
+    }
+
+    pub fn set(&self, rules: Vec<IpFilterRule>) -> AppResult<()> {
+        let compiled = compile(rules).map_err(AppError::InvalidInput)?;
+        *self.active.write().unwrap() = Arc::new(compiled);
+        tracing::info!(rules = self.rules().len(), "IP filter rules updated");
+        Ok(())
+    }
+
+    pub fn reset(&self) -> AppResult<()> {
+        self.set(self.default_rules.clone())
+    }
+
+    /// Returns the path prefix of the rule that rejects the request, if any.
+    fn denied_by(&self, method: &Method, path: &str, ip: Option<IpAddr>) -> Option<String> {
+        let rules = self.active.read().unwrap().clone();
+        rules
+            .iter()
+            .find(|rule| rule.matches(method, path))
+            .filter(|rule| !rule.permits(ip))
+            .map(|rule| rule.rule.path_prefix.clone())
+    }
+}
+
+/// Rejects requests whose client address is not permitted by the `IpFilter`.
+/// Relies on `ClientInfoLayer` having resolved the client address.
+#[derive(Clone)]
+pub struct IpFilterLayer {
+    filter: Arc<IpFilter>,
+}
+
+impl IpFilterLayer {
+    pub fn new(filter: Arc<IpFilter>) -> Self {
+        Self { filter }
+    }
+}
+
+impl<S> Layer<S> for IpFilterLayer {
+    type Service = IpFilterService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        IpFilterService {
+            inner,
+            filter: self.filter.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct IpFilterService<S> {
+    inner: S,
+    filter: Arc<IpFilter>,
+}
+
+impl<S> Service<Request> for IpFilterService<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let ip = req.extensions().get::<ClientInfo>().and_then(|client| client.ip);
+
+        if let Some(rule) = self.filter.denied_by(req.method(), req.uri().path(), ip) {
+            tracing::warn!(
+                client_ip = %ip.map_or_else(|| "unknown".to_string(), |ip| ip.to_string()),
+                method = %req.method(),
+                path = %req.uri().path(),
+                rule = %rule,
+                "Request denied by IP filter"
+            );
+            let response = AppError::Forbidden("Access from this address is not allowed".to_string()).into_response();
+            return Box::pin(async move { Ok(response) });
+        }
+
+        let mut inner = self.inner.clone();
+        Box::pin(async move { inner.call(req).await })
+    }
+}
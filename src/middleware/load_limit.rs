@@ -0,0 +1,204 @@
+use crate::config::{LimitsConfig, RouteTimeout};
+use crate::domain::errors::AppError;
+use crate::utils::network::path_has_prefix;
+use axum::{
+    extract::Request,
+    http::{header, HeaderValue, Method},
+    response::{IntoResponse, Response},
+};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    time::{timeout_at, Instant},
+};
+use tower::{Layer, Service};
+
+// Weight of a new sample in the queue wait average, as a power of two (1/8)
+const EWMA_SHIFT: u32 = 3;
+
+/// Applies per-route timeouts, a global concurrency limit and adaptive load
+/// shedding to the requests passing through it.
+#[derive(Clone)]
+pub struct LoadLimitLayer {
+    limiter: Arc<LoadLimiter>,
+}
+
+impl LoadLimitLayer {
+    pub fn new(config: &LimitsConfig) -> Self {
+        Self {
+            limiter: Arc::new(LoadLimiter {
+                default_timeout: Duration::from_millis(config.request_timeout_ms),
+                route_timeouts: config.route_timeouts.clone(),
+                semaphore: (config.max_concurrent_requests > 0)
+                    .then(|| Arc::new(Semaphore::new(config.max_concurrent_requests))),
+                shed_threshold_us: config.shed_queue_wait_ms * 1000,
+                queue_wait_us: AtomicU64::new(0),
+                shed_total: AtomicU64::new(0),
+                timeout_total: AtomicU64::new(0),
+            }),
+        }
+    }
+}
+
+impl<S> Layer<S> for LoadLimitLayer {
+    type Service = LoadLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        LoadLimitService {
+            inner,
+            limiter: self.limiter.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct LoadLimitService<S> {
+    inner: S,
+    limiter: Arc<LoadLimiter>,
+}
+
+impl<S> Service<Request> for LoadLimitService<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let mut inner = self.inner.clone();
+        let limiter = self.limiter.clone();
+
+        Box::pin(async move {
+            let timeout = limiter.timeout_for(req.method(), req.uri().path());
+            let deadline = Instant::now() + timeout;
+
+            // Held until the handler has produced its response
+            let _permit = match limiter.acquire(deadline, timeout).await {
+                Ok(permit) => permit,
+                Err(error @ AppError::ServiceUnavailable(_)) => {
+                    return Ok(with_retry_after(error.into_response()))
+                }
+                Err(error) => return Ok(error.into_response()),
+            };
+
+            match timeout_at(deadline, inner.call(req)).await {
+                Ok(result) => result,
+                Err(_) => Ok(limiter.timed_out("handler", timeout).into_response()),
+            }
+        })
+    }
+}
+
+struct LoadLimiter {
+    default_timeout: Duration,
+    route_timeouts: Vec<RouteTimeout>,
+    semaphore: Option<Arc<Semaphore>>,
+    shed_threshold_us: u64,
+    /// Exponentially weighted moving average of the time spent waiting for a permit.
+    queue_wait_us: AtomicU64,
+    shed_total: AtomicU64,
+    timeout_total: AtomicU64,
+}
+
+impl LoadLimiter {
+    fn timeout_for(&self, method: &Method, path: &str) -> Duration {
+        self.route_timeouts
+            .iter()
+            .find(|route| {
+                path_has_prefix(path, &route.path_prefix)
+                    && (route.methods.is_empty() || route.methods.iter().any(|m| m.eq_ignore_ascii_case(method.as_str())))
+            })
+            .map_or(self.default_timeout, |route| Duration::from_millis(route.timeout_ms))
+    }
+
+    /// Waits for a concurrency permit until `deadline`, or rejects the request
+    /// straight away when the server is saturated and the queue is already slow.
+    /// A request still queued at its deadline has timed out like one whose
+    /// handler ran too long.
+    async fn acquire(
+        &self,
+        deadline: Instant,
+        timeout: Duration,
+    ) -> Result<Option<OwnedSemaphorePermit>, AppError> {
+        let Some(semaphore) = &self.semaphore else {
+            return Ok(None);
+        };
+
+        if let Ok(permit) = semaphore.clone().try_acquire_owned() {
+            // Uncontended acquisitions pull the average back down once load eases
+            self.record_queue_wait(Duration::ZERO);
+            return Ok(Some(permit));
+        }
+
+        let average_wait_us = self.queue_wait_us.load(Ordering::Relaxed);
+        if average_wait_us > self.shed_threshold_us {
+            return Err(self.shed("queue_latency", average_wait_us));
+        }
+
+        let started = Instant::now();
+        let acquired = timeout_at(deadline, semaphore.clone().acquire_owned()).await;
+        self.record_queue_wait(started.elapsed());
+
+        match acquired {
+            Ok(Ok(permit)) => Ok(Some(permit)),
+            Ok(Err(_)) => Err(self.shed("semaphore_closed", self.queue_wait_us.load(Ordering::Relaxed))),
+            Err(_) => Err(self.timed_out("queue", timeout)),
+        }
+    }
+
+    fn record_queue_wait(&self, wait: Duration) {
+        let sample = wait.as_micros() as u64;
+        let _ = self
+            .queue_wait_us
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |average| {
+                Some(average - (average >> EWMA_SHIFT) + (sample >> EWMA_SHIFT))
+            });
+    }
+
+    /// `stage` is where the request was when its deadline passed: "queue" or "handler".
+    fn timed_out(&self, stage: &'static str, timeout: Duration) -> AppError {
+        let timeout_total = self.timeout_total.fetch_add(1, Ordering::Relaxed) + 1;
+        tracing::warn!(
+            event = "request_timeout",
+            stage,
+            timeout_ms = timeout.as_millis() as u64,
+            timeout_total,
+            "Request timed out"
+        );
+        AppError::Timeout(format!("exceeded {:?}", timeout))
+    }
+
+    fn shed(&self, reason: &'static str, average_wait_us: u64) -> AppError {
+        let shed_total = self.shed_total.fetch_add(1, Ordering::Relaxed) + 1;
+        tracing::warn!(
+            event = "request_shed",
+            reason,
+            queue_wait_avg_ms = average_wait_us / 1000,
+            shed_total,
+            "Request rejected to shed load"
+        );
+        AppError::ServiceUnavailable(reason.to_string())
+    }
+}
+
+fn with_retry_after(mut response: Response) -> Response {
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from_static("1"));
+    response
+}
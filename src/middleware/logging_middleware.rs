@@ -1,3 +1,6 @@
+use crate::middleware::client_info::ClientInfo;
+use crate::server::tls::ClientIdentity;
+use crate::telemetry::redaction;
 use axum::{extract::Request, response::Response};
 use std::{
     future::Future,
@@ -5,6 +8,7 @@ use std::{
     task::{Context, Poll},
 };
 use tower::{Layer, Service};
+use tracing::Instrument;
 use uuid::Uuid;
 
 #[derive(Clone)]
@@ -72,15 +76,29 @@ where
             correlation_id = %correlation_id,
             request_id= %request_id,
             method = %req.method(),
-            uri = %req.uri(),
+            uri = %redaction::uri(req.uri()),
             version = ?req.version(),
+            client_ip = tracing::field::Empty,
+            scheme = tracing::field::Empty,
+            host = tracing::field::Empty,
+            client_identity = tracing::field::Empty,
         );
+        if let Some(client) = req.extensions().get::<ClientInfo>() {
+            span.record("client_ip", tracing::field::display(client));
+            span.record("scheme", tracing::field::display(&client.scheme));
+            if let Some(host) = &client.host {
+                span.record("host", tracing::field::display(host));
+            }
+        }
+        // Subjects and SANs can name a person or carry an email address
+        if let Some(identity) = req.extensions().get::<ClientIdentity>() {
+            span.record("client_identity", redaction::pii(&identity.to_string()));
+        }
 
         let mut inner = self.inner.clone();
+        // Instrument the future with the span - all logs within this request will include these fields.
+        // Entering it inside the async block would leak it into other requests polled on the same thread.
         Box::pin(async move {
-            // Enter the span - all logs within this request will include these fields
-            let _guard = span.enter();
-            
             tracing::info!("Request started");
             
             let start_time = std::time::Instant::now();
@@ -112,6 +130,7 @@ where
             } else {
                 result
             }
-        })
+        }
+        .instrument(span))
     }
 }
\ No newline at end of file
@@ -1 +1,12 @@
-pub mod logging_middleware;
\ No newline at end of file
+pub mod access_log;
+pub mod body;
+pub mod capture;
+pub mod client_auth;
+pub mod client_info;
+pub mod compression;
+pub mod ip_filter;
+pub mod load_limit;
+pub mod logging_middleware;
+pub mod panic;
+pub mod problem_details;
+pub mod security_headers;
@@ -0,0 +1,37 @@
+use crate::domain::errors::AppError;
+use axum::response::{IntoResponse, Response};
+use std::{any::Any, backtrace::Backtrace, panic::PanicHookInfo};
+
+/// Routes panic reports through tracing, so they carry the current request's
+/// span fields (including the correlation id) instead of going to stderr.
+pub fn install_panic_hook() {
+    std::panic::set_hook(Box::new(|info: &PanicHookInfo<'_>| {
+        let location = info
+            .location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()))
+            .unwrap_or_default();
+
+        tracing::error!(
+            panic_message = %payload_message(info.payload()),
+            panic_location = %location,
+            backtrace = %Backtrace::force_capture(),
+            "Panic occurred"
+        );
+    }));
+}
+
+/// Used with `CatchPanicLayer::custom` to turn a panicking handler into the
+/// standard 500 error envelope. The panic itself is logged by the panic hook.
+pub fn panic_response(payload: Box<dyn Any + Send + 'static>) -> Response {
+    AppError::Internal(format!("Handler panicked: {}", payload_message(payload.as_ref()))).into_response()
+}
+
+fn payload_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic payload"
+    }
+}
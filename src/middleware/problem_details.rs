@@ -0,0 +1,154 @@
+use crate::config::ErrorsConfig;
+use crate::domain::errors::{ErrorDetails, ProblemDetails};
+use anyhow::{bail, Result};
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::Response,
+};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+
+const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Re-renders error responses as RFC 9457 problem details when the client
+/// accepts `application/problem+json` or `errors.format` is `problem`.
+/// Must run inside `LoggingMiddlewareLayer` so the correlation id is known.
+#[derive(Clone)]
+pub struct ProblemDetailsLayer {
+    always: bool,
+    type_base: Arc<str>,
+}
+
+impl ProblemDetailsLayer {
+    pub fn new(config: &ErrorsConfig) -> Result<Self> {
+        let always = match config.format.as_str() {
+            "json" => false,
+            "problem" => true,
+            other => bail!("Unknown error format '{}', expected 'json' or 'problem'", other),
+        };
+        Ok(Self {
+            always,
+            type_base: config.problem_type_base.as_str().into(),
+        })
+    }
+}
+
+impl<S> Layer<S> for ProblemDetailsLayer {
+    type Service = ProblemDetailsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ProblemDetailsService {
+            inner,
+            always: self.always,
+            type_base: self.type_base.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ProblemDetailsService<S> {
+    inner: S,
+    always: bool,
+    type_base: Arc<str>,
+}
+
+impl<S> Service<Request> for ProblemDetailsService<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let mut inner = self.inner.clone();
+        if !self.always && !accepts_problem(req.headers()) {
+            return Box::pin(async move { inner.call(req).await });
+        }
+
+        let type_base = self.type_base.clone();
+        let instance = req.uri().path().to_string();
+        let correlation_id = req
+            .headers()
+            .get("x-correlation-id")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+
+        Box::pin(async move {
+            let response = inner.call(req).await?;
+            let Some(details) = response.extensions().get::<ErrorDetails>().cloned() else {
+                return Ok(response);
+            };
+
+            let (mut parts, _) = response.into_parts();
+            let problem = ProblemDetails {
+                problem_type: problem_type(&type_base, &details),
+                title: title(&type_base, &details, parts.status),
+                status: parts.status.as_u16(),
+                detail: details.message,
+                instance,
+                code: details.code,
+                correlation_id,
+                errors: details.errors,
+            };
+            let body = serde_json::to_vec(&problem).unwrap_or_default();
+
+            // The original body may have been compressed on the way out
+            parts.headers.remove(header::CONTENT_ENCODING);
+            parts.headers.remove(header::CONTENT_LENGTH);
+            parts
+                .headers
+                .insert(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+            Ok(Response::from_parts(parts, Body::from(body)))
+        })
+    }
+}
+
+/// True when `Accept` lists `application/problem+json` with a non-zero quality.
+fn accepts_problem(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|range| {
+            let mut params = range.split(';').map(str::trim);
+            let media_type = params.next().unwrap_or_default();
+            let rejected = params.any(|param| {
+                param
+                    .strip_prefix("q=")
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .is_some_and(|q| q == 0.0)
+            });
+            media_type.eq_ignore_ascii_case(PROBLEM_JSON) && !rejected
+        })
+}
+
+fn problem_type(type_base: &str, details: &ErrorDetails) -> String {
+    if type_base.is_empty() {
+        "about:blank".to_string()
+    } else {
+        format!("{}{}", type_base, details.code.slug())
+    }
+}
+
+/// RFC 9457 asks for the status phrase as the title of `about:blank` problems.
+fn title(type_base: &str, details: &ErrorDetails, status: StatusCode) -> String {
+    if type_base.is_empty() {
+        status.canonical_reason().unwrap_or_default().to_string()
+    } else {
+        details.code.title().to_string()
+    }
+}
@@ -0,0 +1,172 @@
+use crate::config::SecurityHeadersConfig;
+use crate::utils::network::path_has_prefix;
+use anyhow::{Context as _, Result};
+use axum::{
+    extract::Request,
+    http::{header, HeaderName, HeaderValue},
+    response::Response,
+};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+
+const PERMISSIONS_POLICY: &str =
+    "accelerometer=(), camera=(), geolocation=(), gyroscope=(), microphone=(), payment=(), usb=()";
+const CONTENT_SECURITY_POLICY: &str = "default-src 'none'; frame-ancestors 'none'; base-uri 'none'; form-action 'none'";
+// Swagger UI styles elements inline and embeds its icons as data URIs
+const DOCS_PATH: &str = "/api/v1/docs";
+const DOCS_CONTENT_SECURITY_POLICY: &str = "default-src 'self'; img-src 'self' data:; style-src 'self' 'unsafe-inline'; \
+     frame-ancestors 'none'; base-uri 'none'; form-action 'none'";
+
+/// Adds security headers to every response and strips the `Server` header.
+/// Headers already set by a handler are left alone.
+#[derive(Clone)]
+pub struct SecurityHeadersLayer {
+    policy: Option<Arc<SecurityHeaders>>,
+}
+
+struct SecurityHeaders {
+    headers: Vec<(HeaderName, HeaderValue)>,
+    csp_header: HeaderName,
+    csp: Option<HeaderValue>,
+    csp_overrides: Vec<(String, HeaderValue)>,
+}
+
+impl SecurityHeadersLayer {
+    pub fn new(config: &SecurityHeadersConfig, environment: &str) -> Result<Self> {
+        if !config.enabled {
+            return Ok(Self { policy: None });
+        }
+
+        let production = environment == "production";
+        // HSTS would pin plain-HTTP development hosts such as localhost to HTTPS
+        let (default_hsts, default_referrer_policy) = if production {
+            ("max-age=63072000; includeSubDomains", "no-referrer")
+        } else {
+            ("", "strict-origin-when-cross-origin")
+        };
+        let value = |configured: &Option<String>, default: &str| configured.clone().unwrap_or_else(|| default.to_string());
+
+        let mut headers = Vec::new();
+        for (name, value) in [
+            (header::STRICT_TRANSPORT_SECURITY, value(&config.hsts, default_hsts)),
+            (header::X_CONTENT_TYPE_OPTIONS, value(&config.content_type_options, "nosniff")),
+            (header::X_FRAME_OPTIONS, value(&config.frame_options, "DENY")),
+            (header::REFERRER_POLICY, value(&config.referrer_policy, default_referrer_policy)),
+            (
+                HeaderName::from_static("permissions-policy"),
+                value(&config.permissions_policy, PERMISSIONS_POLICY),
+            ),
+        ] {
+            if let Some(value) = header_value(&name, &value)? {
+                headers.push((name, value));
+            }
+        }
+
+        // Development reports violations without enforcing them
+        let csp_header = if config.csp_report_only.unwrap_or(!production) {
+            header::CONTENT_SECURITY_POLICY_REPORT_ONLY
+        } else {
+            header::CONTENT_SECURITY_POLICY
+        };
+        let csp = header_value(
+            &csp_header,
+            &value(&config.content_security_policy, CONTENT_SECURITY_POLICY),
+        )?;
+        let mut csp_overrides = config
+            .csp_overrides
+            .iter()
+            .map(|route| {
+                let policy = HeaderValue::from_str(&route.policy)
+                    .with_context(|| format!("Invalid CSP override for {}", route.path_prefix))?;
+                Ok((route.path_prefix.clone(), policy))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        // Configured overrides come first so they can replace the docs policy
+        csp_overrides.push((DOCS_PATH.to_string(), HeaderValue::from_static(DOCS_CONTENT_SECURITY_POLICY)));
+
+        Ok(Self {
+            policy: Some(Arc::new(SecurityHeaders {
+                headers,
+                csp_header,
+                csp,
+                csp_overrides,
+            })),
+        })
+    }
+}
+
+fn header_value(name: &HeaderName, value: &str) -> Result<Option<HeaderValue>> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+    HeaderValue::from_str(value)
+        .map(Some)
+        .with_context(|| format!("Invalid value for {} header", name))
+}
+
+impl<S> Layer<S> for SecurityHeadersLayer {
+    type Service = SecurityHeadersService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SecurityHeadersService {
+            inner,
+            policy: self.policy.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SecurityHeadersService<S> {
+    inner: S,
+    policy: Option<Arc<SecurityHeaders>>,
+}
+
+impl<S> Service<Request> for SecurityHeadersService<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let mut inner = self.inner.clone();
+        let Some(policy) = self.policy.clone() else {
+            return Box::pin(async move { inner.call(req).await });
+        };
+
+        let path = req.uri().path().to_string();
+
+        Box::pin(async move {
+            let mut response = inner.call(req).await?;
+            let headers = response.headers_mut();
+
+            headers.remove(header::SERVER);
+            for (name, value) in &policy.headers {
+                headers.entry(name).or_insert_with(|| value.clone());
+            }
+
+            let csp = policy
+                .csp_overrides
+                .iter()
+                .find(|(prefix, _)| path_has_prefix(&path, prefix))
+                .map(|(_, policy)| policy)
+                .or(policy.csp.as_ref());
+            if let Some(csp) = csp {
+                headers.entry(&policy.csp_header).or_insert_with(|| csp.clone());
+            }
+
+            Ok(response)
+        })
+    }
+}
@@ -0,0 +1,63 @@
+use crate::domain::{
+    errors::{ErrorBody, ErrorCode, ErrorResponse, FieldError, ProblemDetails},
+    requests::{CreateUserRequest, ImportMode, UpdateUserRequest},
+    responses::{
+        ImportJobLinks, ImportJobResponse, ImportJobStatus, ImportRowError, PageLinks, UserLinks,
+        UserProfileExpansion, UserProfileResponse, UserResponse, UsersListResponse,
+    },
+};
+use utoipa::{IntoResponses, OpenApi};
+
+/// The OpenAPI 3.1 description of the public API. Its paths are added by the
+/// `OpenApiRouter`s in `routes`, from the handlers they actually serve.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "HTTP Example Server",
+        description = "User management API. Every error is returned in the `ErrorResponse` envelope; \
+                       `error.code` identifies the failure. \
+                       Clients sending `Accept: application/problem+json` get RFC 9457 `ProblemDetails` instead."
+    ),
+    components(schemas(
+        CreateUserRequest,
+        UpdateUserRequest,
+        UserResponse,
+        UserLinks,
+        UserProfileResponse,
+        UserProfileExpansion,
+        UsersListResponse,
+        PageLinks,
+        ImportMode,
+        ImportJobStatus,
+        ImportJobResponse,
+        ImportJobLinks,
+        ImportRowError,
+        ErrorResponse,
+        ErrorBody,
+        ErrorCode,
+        FieldError,
+        ProblemDetails,
+    )),
+    tags(
+        (name = "users", description = "User management"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Errors any public endpoint can return, independent of the operation.
+#[derive(IntoResponses)]
+#[allow(dead_code)]
+pub enum CommonErrors {
+    /// `FORBIDDEN`: the client address or certificate is not allowed to call this route.
+    #[response(status = 403)]
+    Forbidden(ErrorResponse),
+    /// `DATABASE_ERROR` or `INTERNAL_ERROR`.
+    #[response(status = 500)]
+    Internal(ErrorResponse),
+    /// `SERVICE_UNAVAILABLE`: the server is shedding load; retry after the `Retry-After` delay.
+    #[response(status = 503)]
+    Unavailable(ErrorResponse),
+    /// `REQUEST_TIMEOUT`: the request did not complete within its time budget.
+    #[response(status = 504)]
+    Timeout(ErrorResponse),
+}
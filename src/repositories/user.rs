@@ -1,16 +1,46 @@
 use crate::domain::{entities::User, errors::AppResult};
-use std::collections::HashMap;
-use std::sync::Mutex;
+use std::collections::BTreeMap;
+use std::ops::Bound;
+use std::sync::{Mutex, MutexGuard};
 use uuid::Uuid;
 
+/// How the repository reacts when its lock was poisoned by a panicking thread.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LockPoisonPolicy {
+    /// Clear the poison and keep serving the data. Every operation leaves the
+    /// map consistent before anything that could panic, so this is safe.
+    #[default]
+    Recover,
+    /// Fail every subsequent operation with a database error.
+    Fail,
+}
+
+impl std::str::FromStr for LockPoisonPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "recover" => Ok(Self::Recover),
+            "fail" => Ok(Self::Fail),
+            other => Err(format!("Unknown lock poison policy: {}", other)),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct UserRepository {
-    users: Mutex<HashMap<Uuid, User>>,
+    /// Ordered by id, so the store can be paged through with an id cursor.
+    users: Mutex<BTreeMap<Uuid, User>>,
+    lock_poison_policy: LockPoisonPolicy,
 }
 
 impl UserRepository {
     pub fn new() -> Self {
-        let mut users = HashMap::new();
+        Self::with_lock_poison_policy(LockPoisonPolicy::default())
+    }
+
+    pub fn with_lock_poison_policy(lock_poison_policy: LockPoisonPolicy) -> Self {
+        let mut users = BTreeMap::new();
         
         // Add sample data
         let user1 = User::new(
@@ -29,11 +59,28 @@ impl UserRepository {
 
         Self {
             users: Mutex::new(users),
+            lock_poison_policy,
         }
     }
 
+    fn lock(&self) -> AppResult<MutexGuard<'_, BTreeMap<Uuid, User>>> {
+        self.users.lock().or_else(|poisoned| match self.lock_poison_policy {
+            LockPoisonPolicy::Recover => {
+                tracing::warn!("User store lock was poisoned by a panic, recovering");
+                self.users.clear_poison();
+                Ok(poisoned.into_inner())
+            }
+            LockPoisonPolicy::Fail => {
+                tracing::error!("User store lock was poisoned by a panic");
+                Err(crate::domain::errors::AppError::Database(
+                    "User store lock poisoned".to_string(),
+                ))
+            }
+        })
+    }
+
     pub fn create(&self, user: User) -> AppResult<User> {
-        let mut users = self.users.lock().unwrap();
+        let mut users = self.lock()?;
         
         // Check if email already exists
         if users.values().any(|u| u.email == user.email) {
@@ -45,7 +92,7 @@ impl UserRepository {
     }
 
     pub fn find_by_id(&self, id: Uuid) -> AppResult<User> {
-        let users = self.users.lock().unwrap();
+        let users = self.lock()?;
         users
             .get(&id)
             .cloned()
@@ -53,14 +100,30 @@ impl UserRepository {
     }
 
     pub fn find_all(&self) -> AppResult<Vec<User>> {
-        let users = self.users.lock().unwrap();
-        let mut user_list: Vec<User> = users.values().cloned().collect();
-        user_list.sort_by(|a, b| a.name.cmp(&b.name));
-        Ok(user_list)
+        let users = self.lock()?;
+        // Unordered; callers sort with the collation they need
+        Ok(users.values().cloned().collect())
+    }
+
+    pub fn find_by_email(&self, email: &str) -> AppResult<Option<User>> {
+        let users = self.lock()?;
+        Ok(users.values().find(|u| u.email == email).cloned())
+    }
+
+    /// Returns up to `limit` users in id order, starting after the `after` id.
+    /// The lock is only held per page, so writes proceed between calls.
+    pub fn find_page_after(&self, after: Option<Uuid>, limit: usize) -> AppResult<Vec<User>> {
+        let users = self.lock()?;
+        let start = after.map_or(Bound::Unbounded, Bound::Excluded);
+        Ok(users
+            .range((start, Bound::Unbounded))
+            .map(|(_, user)| user.clone())
+            .take(limit)
+            .collect())
     }
 
     pub fn update(&self, id: Uuid, mut user: User) -> AppResult<User> {
-        let mut users = self.users.lock().unwrap();
+        let mut users = self.lock()?;
         
         // Check if user exists
         if !users.contains_key(&id) {
@@ -78,16 +141,16 @@ impl UserRepository {
     }
 
     pub fn delete(&self, id: Uuid) -> AppResult<()> {
-        let mut users = self.users.lock().unwrap();
+        let mut users = self.lock()?;
         users
             .remove(&id)
             .ok_or(crate::domain::errors::AppError::UserNotFound(id))?;
         Ok(())
     }
 
-    pub fn count(&self) -> usize {
-        let users = self.users.lock().unwrap();
-        users.len()
+    pub fn count(&self) -> AppResult<usize> {
+        let users = self.lock()?;
+        Ok(users.len())
     }
 }
 
@@ -0,0 +1,22 @@
+use crate::handlers::admin;
+use crate::AdminState;
+use axum::{routing::get, Router};
+
+pub fn routes() -> Router<AdminState> {
+    Router::new()
+        .route(
+            "/log-level",
+            get(admin::get_log_level)
+                .put(admin::update_log_level)
+                .delete(admin::reset_log_level),
+        )
+        .route(
+            "/ip-filter",
+            get(admin::get_ip_filter)
+                .put(admin::update_ip_filter)
+                .delete(admin::reset_ip_filter),
+        )
+        .route("/captures", get(admin::list_captures).delete(admin::clear_captures))
+        .route("/captures/har", get(admin::export_captures_har))
+        .route("/captures/:id", get(admin::get_capture))
+}
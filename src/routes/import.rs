@@ -0,0 +1,15 @@
+use crate::handlers::import;
+use crate::services::import::ImportService;
+use axum::extract::DefaultBodyLimit;
+use std::sync::Arc;
+use utoipa_axum::{
+    router::{OpenApiRouter, UtoipaMethodRouterExt},
+    routes,
+};
+
+pub fn routes(max_upload_bytes: usize) -> OpenApiRouter<Arc<ImportService>> {
+    OpenApiRouter::new()
+        .routes(routes!(import::import_users).layer(DefaultBodyLimit::max(max_upload_bytes)))
+        .routes(routes!(import::get_import_job))
+        .routes(routes!(import::get_import_errors))
+}
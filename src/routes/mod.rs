@@ -1,36 +1,110 @@
+mod admin;
 mod health;
+mod import;
+mod static_files;
 mod user;
 
-use crate::middleware::logging_middleware::LoggingMiddlewareLayer;
-use crate::services::user::UserService;
+use crate::config::AppConfig;
+use crate::middleware::{
+    access_log::AccessLogLayer, body, capture::CaptureLayer, client_auth::ClientAllowlistLayer,
+    client_info::ClientInfoLayer, compression, ip_filter::{IpFilter, IpFilterLayer},
+    load_limit::LoadLimitLayer, logging_middleware::LoggingMiddlewareLayer, panic,
+    problem_details::ProblemDetailsLayer, security_headers::SecurityHeadersLayer,
+};
+use crate::openapi::ApiDoc;
+use crate::services::{import::ImportService, user::UserService};
+use crate::telemetry::{access_log::AccessLogger, capture::CaptureStore};
+use crate::AdminState;
 use axum::Router;
 use std::sync::Arc;
 use tower::ServiceBuilder;
-use tower_http::{cors::CorsLayer, trace::TraceLayer};
+use tower_http::{catch_panic::CatchPanicLayer, cors::CorsLayer, trace::TraceLayer};
+use utoipa::OpenApi;
+use utoipa_axum::router::OpenApiRouter;
+use utoipa_swagger_ui::SwaggerUi;
 
-pub fn create_routes(user_service: Arc<UserService>) -> Router {
-    // Create stateless health routes
-    let health_routes = health::routes();
-    
+pub fn create_routes(
+    user_service: Arc<UserService>,
+    import_service: Arc<ImportService>,
+    access_logger: Option<AccessLogger>,
+    captures: Arc<CaptureStore>,
+    ip_filter: Arc<IpFilter>,
+    config: &AppConfig,
+) -> anyhow::Result<Router> {
     // Create user routes with specific state type and provide the state
     let user_routes = user::routes().with_state(user_service.clone());
-    
-    // Convert health routes to have the same state type
-    // let health_routes_with_state = health_routes.with_state(user_service.clone());
-    
-    // Combine routes - now both have the same state type
-    let api_routes = Router::new()
-        .nest("/health", health_routes)
-        .nest("/users", user_routes);
+    let import_routes = import::routes(config.import.max_upload_bytes).with_state(import_service);
 
-    Router::new()
-        .route("/", axum::routing::get(crate::handlers::health::root))
+    let api_routes = OpenApiRouter::new()
+        .nest("/users", user_routes)
+        .nest("/users/import", import_routes)
+        .fallback(crate::handlers::fallback::api_not_found);
+    // The documented paths are the ones the router serves
+    let (api_routes, openapi) = OpenApiRouter::with_openapi(ApiDoc::openapi())
         .nest("/api/v1", api_routes)
-        .layer(
+        .split_for_parts();
+
+    // Without client certificates no request could pass the allowlist, and
+    // silently dropping it would leave the routes open
+    let client_auth = &config.tls.client_auth;
+    let certificates_requested = config.tls.enabled && client_auth.is_enabled();
+    if !client_auth.allowlist.is_empty() && !certificates_requested {
+        anyhow::bail!("tls.client_auth.allowlist is set but client certificates are not requested; enable tls and set tls.client_auth.mode");
+    }
+
+    let mut router = api_routes
+        // The document is generated once here and served from memory
+        .merge(SwaggerUi::new("/api/v1/docs").url("/api/v1/openapi.json", openapi));
+    if config.static_files.enabled {
+        router = static_files::mount(router, &config.static_files)?;
+    }
+    // A single-page app mounted at the root serves its own index.html there
+    if !(config.static_files.enabled && static_files::mounted_at_root(&config.static_files)) {
+        router = router.route("/", axum::routing::get(crate::handlers::health::root));
+    }
+
+    let router = router.layer(
             ServiceBuilder::new()
                 .layer(TraceLayer::new_for_http())
+                .layer(ClientInfoLayer::new(&config.proxy)?)
+                .layer(SecurityHeadersLayer::new(&config.security_headers, &config.environment)?)
                 .layer(LoggingMiddlewareLayer::new())
+                .layer(ProblemDetailsLayer::new(&config.errors)?)
+                .map_response(body::into_axum_response)
+                .layer(AccessLogLayer::new(access_logger))
+                .layer(IpFilterLayer::new(ip_filter))
+                .layer(LoadLimitLayer::new(&config.limits))
+                // Capture sees bodies as the handlers do, before compression
+                .map_response(body::into_axum_response)
+                .layer(compression::compression_layer(&config.compression)?)
+                .map_response(compression::with_content_length)
+                .layer(compression::decompression_layer(&config.compression))
+                .map_request(body::into_axum_request)
+                .layer(CaptureLayer::new(captures))
                 .layer(CorsLayer::permissive())
+                .layer(ClientAllowlistLayer::new(client_auth.allowlist.clone()))
+                // Innermost, so a handler panic still gets an access log line
+                // and a capture entry like any other 500
+                .map_response(body::into_axum_response)
+                .layer(CatchPanicLayer::custom(panic::panic_response))
+                .into_inner(),
+        );
+
+    Ok(router)
+}
+
+/// Operational endpoints, served on the separate admin listener so they can be
+/// firewalled independently of the public API.
+pub fn create_admin_routes(admin_state: AdminState) -> Router {
+    Router::new()
+        .nest("/health", health::routes())
+        .merge(admin::routes().with_state(admin_state))
+        .layer(
+            ServiceBuilder::new()
+                .layer(TraceLayer::new_for_http())
+                .layer(LoggingMiddlewareLayer::new())
+                .map_response(body::into_axum_response)
+                .layer(CatchPanicLayer::custom(panic::panic_response))
                 .into_inner(),
         )
-}
\ No newline at end of file
+}
@@ -0,0 +1,124 @@
+use crate::config::StaticFilesConfig;
+use anyhow::{bail, Result};
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Router,
+};
+use std::{convert::Infallible, path::Path, sync::Arc};
+use tower::{service_fn, ServiceExt};
+use tower_http::services::{ServeDir, ServeFile};
+
+const NO_CACHE: HeaderValue = HeaderValue::from_static("no-cache");
+const IMMUTABLE: HeaderValue = HeaderValue::from_static("public, max-age=31536000, immutable");
+
+/// Whether the directory is served for every path not taken by a route.
+pub fn mounted_at_root(config: &StaticFilesConfig) -> bool {
+    config.mount_path.trim_end_matches('/').is_empty()
+}
+
+/// Mounts the static file directory on `router` at the configured path.
+pub fn mount(router: Router, config: &StaticFilesConfig) -> Result<Router> {
+    let mount_path = config.mount_path.trim_end_matches('/');
+    if !config.mount_path.starts_with('/') {
+        bail!("Static files mount path must start with '/': {}", config.mount_path);
+    }
+    if mount_path == "/api" || mount_path.starts_with("/api/") {
+        bail!("Static files cannot be mounted under /api: {}", config.mount_path);
+    }
+
+    let dir = Path::new(&config.dir);
+    if !dir.is_dir() {
+        bail!("Static files directory not found: {}", dir.display());
+    }
+
+    let index = ServeFile::new(dir.join("index.html"))
+        .precompressed_br()
+        .precompressed_gzip();
+    let spa_fallback = config.spa_fallback;
+    let fallback = service_fn(move |req: Request| {
+        let index = index.clone();
+        async move {
+            // Missing assets are real 404s; only extensionless paths are client-side routes
+            if !spa_fallback || has_extension(req.uri().path()) {
+                return Ok::<_, Infallible>(StatusCode::NOT_FOUND.into_response());
+            }
+            Ok(index.oneshot(req).await?.map(Body::new))
+        }
+    });
+
+    let serve_dir = ServeDir::new(dir)
+        .precompressed_br()
+        .precompressed_gzip()
+        .fallback(fallback);
+
+    let cache = Arc::new(CachePolicy {
+        max_age: HeaderValue::from_str(&format!("public, max-age={}", config.max_age_secs))?,
+        immutable_prefixes: config.immutable_prefixes.clone(),
+    });
+    let service = service_fn(move |req: Request| {
+        let serve_dir = serve_dir.clone();
+        let cache = cache.clone();
+        async move {
+            let path = req.uri().path().to_string();
+            let mut response = serve_dir.oneshot(req).await?.map(Body::new);
+            if response.status().is_success() || response.status() == StatusCode::NOT_MODIFIED {
+                response
+                    .headers_mut()
+                    .insert(header::CACHE_CONTROL, cache.for_path(&path));
+            }
+            Ok::<Response, Infallible>(response)
+        }
+    });
+
+    Ok(if mounted_at_root(config) {
+        router.fallback_service(service)
+    } else {
+        router.nest_service(mount_path, service)
+    })
+}
+
+struct CachePolicy {
+    max_age: HeaderValue,
+    immutable_prefixes: Vec<String>,
+}
+
+impl CachePolicy {
+    /// `index.html` must always be revalidated so that a deploy picks up the new
+    /// asset names; hashed assets can be cached forever.
+    fn for_path(&self, path: &str) -> HeaderValue {
+        if !has_extension(path) || path.ends_with("/index.html") {
+            NO_CACHE
+        } else if self.immutable_prefixes.iter().any(|prefix| path.starts_with(prefix.as_str())) || is_hashed(path) {
+            IMMUTABLE
+        } else {
+            self.max_age.clone()
+        }
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+fn has_extension(path: &str) -> bool {
+    file_name(path).contains('.')
+}
+
+/// Recognises names like `app.3f9a1c2e.js` or `chunk-5d41402abc4b2a76.css`:
+/// a name, a separator and a hex hash right before the extension. The hash
+/// needs a letter, so dates and versions (`report-20241018.pdf`) do not count.
+fn is_hashed(path: &str) -> bool {
+    let Some((stem, _extension)) = file_name(path).rsplit_once('.') else {
+        return false;
+    };
+    let Some((name, hash)) = stem.rsplit_once(['.', '-', '_']) else {
+        return false;
+    };
+    !name.is_empty()
+        && hash.len() >= 8
+        && hash.chars().all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+        && hash.chars().any(|c| c.is_ascii_alphabetic())
+}
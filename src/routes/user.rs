@@ -1,19 +1,12 @@
 use crate::handlers::user;
 use crate::services::user::UserService;
-use axum::{
-    routing::{get},
-    Router,
-};
 use std::sync::Arc;
+use utoipa_axum::{router::OpenApiRouter, routes};
 
-pub fn routes() -> Router<Arc<UserService>> {
-    Router::new()
-        .route("/", get(user::list_users).post(user::create_user))
-        .route(
-            "/:id", 
-            get(user::get_user)
-                .put(user::update_user)
-                .delete(user::delete_user)
-        )
-        .route("/:id/profile", get(user::get_user_profile))
+pub fn routes() -> OpenApiRouter<Arc<UserService>> {
+    OpenApiRouter::new()
+        .routes(routes!(user::list_users, user::create_user))
+        .routes(routes!(user::export_users))
+        .routes(routes!(user::get_user, user::update_user, user::delete_user))
+        .routes(routes!(user::get_user_profile))
 }
\ No newline at end of file
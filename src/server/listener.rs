@@ -0,0 +1,140 @@
+use crate::config::{ServerConfig, UnixSocketConfig};
+use anyhow::{bail, Context, Result};
+use listenfd::ListenFd;
+use std::{
+    fmt, io,
+    net::SocketAddr,
+    os::unix::fs::{FileTypeExt, PermissionsExt},
+    path::{Path, PathBuf},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpListener, UnixListener},
+};
+
+/// A bidirectional byte stream accepted from any of the supported listeners.
+pub trait Connection: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T> Connection for T where T: AsyncRead + AsyncWrite + Unpin + Send {}
+
+pub enum Listener {
+    Tcp(TcpListener),
+    Unix { listener: UnixListener, path: PathBuf },
+}
+
+/// Creates the public listener selected by `server.listener` and the admin listener.
+///
+/// Under systemd the first socket-activated descriptor becomes the public listener
+/// and a second one, if passed, the admin listener; otherwise the admin listener
+/// binds `server.admin_address`.
+pub async fn bind(config: &ServerConfig) -> Result<(Listener, Listener)> {
+    let (public, admin) = match config.listener.as_str() {
+        "tcp" => (Listener::Tcp(TcpListener::bind(&config.address).await?), None),
+        "unix" => (bind_unix(&config.unix_socket)?, None),
+        "systemd" => {
+            let mut fds = ListenFd::from_env();
+            let public = take_systemd_listener(&mut fds, 0)?.context("LISTEN_FDS did not pass a listening socket")?;
+            (public, take_systemd_listener(&mut fds, 1)?)
+        }
+        other => bail!("Unsupported listener type: {}", other),
+    };
+
+    let admin = match admin {
+        Some(admin) => admin,
+        None => Listener::Tcp(TcpListener::bind(&config.admin_address).await?),
+    };
+
+    Ok((public, admin))
+}
+
+impl Listener {
+    /// Accepts a connection, returning the peer address for TCP clients.
+    pub async fn accept(&self) -> io::Result<(Box<dyn Connection>, Option<SocketAddr>)> {
+        match self {
+            Self::Tcp(listener) => {
+                let (stream, peer_addr) = listener.accept().await?;
+                Ok((Box::new(stream), Some(peer_addr)))
+            }
+            Self::Unix { listener, .. } => {
+                let (stream, _) = listener.accept().await?;
+                Ok((Box::new(stream), None))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Listener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(listener) => match listener.local_addr() {
+                Ok(addr) => write!(f, "{}", addr),
+                Err(_) => write!(f, "tcp"),
+            },
+            Self::Unix { path, .. } => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+fn bind_unix(config: &UnixSocketConfig) -> Result<Listener> {
+    let path = PathBuf::from(&config.path);
+    remove_stale_socket(&path)?;
+
+    let listener = UnixListener::bind(&path).with_context(|| format!("Failed to bind {}", path.display()))?;
+
+    let mode = u32::from_str_radix(&config.mode, 8)
+        .with_context(|| format!("Invalid unix socket mode: {}", config.mode))?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode))
+        .with_context(|| format!("Failed to set permissions on {}", path.display()))?;
+
+    Ok(Listener::Unix { listener, path })
+}
+
+/// Removes a socket file left behind by a previous process, refusing to touch
+/// regular files or sockets that still have a live server behind them.
+fn remove_stale_socket(path: &Path) -> Result<()> {
+    let metadata = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to inspect {}", path.display())),
+    };
+
+    if !metadata.file_type().is_socket() {
+        bail!("{} exists and is not a socket", path.display());
+    }
+
+    match std::os::unix::net::UnixStream::connect(path) {
+        Ok(_) => bail!("{} is in use by another process", path.display()),
+        Err(_) => {
+            tracing::info!(path = %path.display(), "Removing stale unix socket");
+            std::fs::remove_file(path).with_context(|| format!("Failed to remove {}", path.display()))?;
+            Ok(())
+        }
+    }
+}
+
+/// Takes the socket passed at `index` through the systemd `LISTEN_FDS` protocol.
+fn take_systemd_listener(fds: &mut ListenFd, index: usize) -> Result<Option<Listener>> {
+    if index >= fds.len() {
+        return Ok(None);
+    }
+
+    if let Ok(Some(listener)) = fds.take_tcp_listener(index) {
+        listener.set_nonblocking(true)?;
+        return Ok(Some(Listener::Tcp(TcpListener::from_std(listener)?)));
+    }
+
+    if let Some(listener) = fds.take_unix_listener(index)? {
+        listener.set_nonblocking(true)?;
+        let path = listener
+            .local_addr()?
+            .as_pathname()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        return Ok(Some(Listener::Unix {
+            listener: UnixListener::from_std(listener)?,
+            path,
+        }));
+    }
+
+    bail!("LISTEN_FDS socket {} is neither a TCP nor a unix stream listener", index)
+}
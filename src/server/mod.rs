@@ -0,0 +1,115 @@
+pub mod listener;
+pub mod tls;
+
+use crate::config::LimitsConfig;
+use axum::{extract::ConnectInfo, extract::Request, Router};
+use hyper::body::Incoming;
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo, TokioTimer},
+    server::conn::auto::Builder,
+    service::TowerToHyperService,
+};
+use listener::Listener;
+use std::{net::SocketAddr, time::Duration};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tls::ClientIdentity;
+use tokio_rustls::TlsAcceptor;
+use tower::ServiceExt;
+
+/// Request extension marking requests that arrived over a TLS connection.
+#[derive(Debug, Clone, Copy)]
+pub struct TlsConnection;
+
+/// Accepts connections from `listener` and serves `app` on each of them,
+/// performing a TLS handshake first when an acceptor is given.
+pub async fn serve(
+    listener: Listener,
+    app: Router,
+    tls: Option<TlsAcceptor>,
+    limits: &LimitsConfig,
+) -> anyhow::Result<()> {
+    let handshake_timeout = Duration::from_millis(limits.tls_handshake_timeout_ms);
+    let header_read_timeout = Duration::from_millis(limits.header_read_timeout_ms);
+
+    loop {
+        let (stream, peer_addr) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                // Errors such as EMFILE are transient, back off instead of spinning
+                tracing::error!(error = %e, "Failed to accept connection");
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+
+        let app = app.clone();
+        let tls = tls.clone();
+        tokio::spawn(async move {
+            match tls {
+                // Bound the handshake so clients that connect and go quiet
+                // do not hold a task and a file descriptor forever
+                Some(acceptor) => match tokio::time::timeout(handshake_timeout, acceptor.accept(stream))
+                    .await
+                {
+                    Ok(Ok(tls_stream)) => {
+                        let identity = tls_stream
+                            .get_ref()
+                            .1
+                            .peer_certificates()
+                            .and_then(|certs| certs.first())
+                            .and_then(ClientIdentity::from_certificate);
+                        serve_connection(tls_stream, peer_addr, true, identity, header_read_timeout, app)
+                            .await
+                    }
+                    Ok(Err(e)) => {
+                        tracing::debug!(peer_addr = ?peer_addr, error = %e, "TLS handshake failed");
+                    }
+                    Err(_) => {
+                        tracing::debug!(peer_addr = ?peer_addr, "TLS handshake timed out");
+                    }
+                },
+                None => serve_connection(stream, peer_addr, false, None, header_read_timeout, app).await,
+            }
+        });
+    }
+}
+
+async fn serve_connection<I>(
+    io: I,
+    peer_addr: Option<SocketAddr>,
+    tls: bool,
+    identity: Option<ClientIdentity>,
+    header_read_timeout: Duration,
+    app: Router,
+) where
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let service = app.map_request(move |mut req: Request<Incoming>| {
+        // Unix socket clients have no peer address
+        if let Some(peer_addr) = peer_addr {
+            req.extensions_mut().insert(ConnectInfo(peer_addr));
+        }
+        if tls {
+            req.extensions_mut().insert(TlsConnection);
+        }
+        if let Some(identity) = &identity {
+            req.extensions_mut().insert(identity.clone());
+        }
+        req
+    });
+
+    let mut builder = Builder::new(TokioExecutor::new());
+    // The header read timeout needs a timer to fire; without one hyper never
+    // closes connections that trickle in their request headers
+    builder
+        .http1()
+        .timer(TokioTimer::new())
+        .header_read_timeout(header_read_timeout);
+
+    if let Err(e) = builder
+        .serve_connection_with_upgrades(TokioIo::new(io), TowerToHyperService::new(service))
+        .await
+    {
+        tracing::debug!(peer_addr = ?peer_addr, error = %e, "Connection closed with error");
+    }
+}
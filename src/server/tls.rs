@@ -0,0 +1,278 @@
+use crate::config::{ClientAuthConfig, TlsConfig};
+use crate::domain::errors::AppError;
+use anyhow::{anyhow, bail, Context, Result};
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+use rustls::{
+    crypto::ring::{default_provider, sign::any_supported_type},
+    pki_types::CertificateDer,
+    server::{danger::ClientCertVerifier, ClientHello, ResolvesServerCert, WebPkiClientVerifier},
+    sign::CertifiedKey,
+    RootCertStore, ServerConfig, SupportedProtocolVersion,
+};
+use std::{
+    fmt,
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+use tokio_rustls::TlsAcceptor;
+
+/// Builds a TLS acceptor from the configured PEM files and, if enabled,
+/// starts a background task that reloads them when they change on disk.
+pub fn acceptor(config: &TlsConfig) -> Result<TlsAcceptor> {
+    let cert_path = PathBuf::from(&config.cert_path);
+    let key_path = PathBuf::from(&config.key_path);
+
+    let resolver = Arc::new(ReloadingCertResolver::new(load_certified_key(&cert_path, &key_path)?));
+
+    let builder = ServerConfig::builder_with_provider(Arc::new(default_provider()))
+        .with_protocol_versions(protocol_versions(&config.min_version)?)?;
+    let builder = match client_verifier(&config.client_auth)? {
+        Some(verifier) => builder.with_client_cert_verifier(verifier),
+        None => builder.with_no_client_auth(),
+    };
+    let mut server_config = builder.with_cert_resolver(resolver.clone());
+    server_config.alpn_protocols = config
+        .alpn_protocols
+        .iter()
+        .map(|protocol| protocol.as_bytes().to_vec())
+        .collect();
+
+    if config.reload_interval_secs > 0 {
+        spawn_reloader(
+            resolver,
+            cert_path,
+            key_path,
+            Duration::from_secs(config.reload_interval_secs),
+        );
+    }
+
+    tracing::info!(
+        cert_path = %config.cert_path,
+        min_version = %config.min_version,
+        alpn = ?config.alpn_protocols,
+        client_auth = %config.client_auth.mode,
+        "TLS enabled"
+    );
+
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+fn protocol_versions(min_version: &str) -> Result<&'static [&'static SupportedProtocolVersion]> {
+    static TLS12_AND_UP: &[&SupportedProtocolVersion] = &[&rustls::version::TLS13, &rustls::version::TLS12];
+    static TLS13_ONLY: &[&SupportedProtocolVersion] = &[&rustls::version::TLS13];
+
+    match min_version {
+        "1.2" => Ok(TLS12_AND_UP),
+        "1.3" => Ok(TLS13_ONLY),
+        other => bail!("Unsupported minimum TLS version: {}", other),
+    }
+}
+
+fn client_verifier(config: &ClientAuthConfig) -> Result<Option<Arc<dyn ClientCertVerifier>>> {
+    let optional = match config.mode.as_str() {
+        "none" => return Ok(None),
+        "optional" => true,
+        "required" => false,
+        other => bail!("Unsupported client auth mode: {}", other),
+    };
+
+    let mut ca_reader = BufReader::new(
+        File::open(&config.ca_path).with_context(|| format!("Failed to open client CA bundle {}", config.ca_path))?,
+    );
+    let mut roots = RootCertStore::empty();
+    for cert in rustls_pemfile::certs(&mut ca_reader) {
+        let cert = cert.with_context(|| format!("Failed to parse client CA bundle {}", config.ca_path))?;
+        roots.add(cert)?;
+    }
+    if roots.is_empty() {
+        bail!("No CA certificates found in {}", config.ca_path);
+    }
+
+    let builder = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), Arc::new(default_provider()));
+    let builder = if optional { builder.allow_unauthenticated() } else { builder };
+
+    Ok(Some(builder.build()?))
+}
+
+fn load_certified_key(cert_path: &Path, key_path: &Path) -> Result<CertifiedKey> {
+    let mut cert_reader = BufReader::new(
+        File::open(cert_path).with_context(|| format!("Failed to open certificate {}", cert_path.display()))?,
+    );
+    let certs = rustls_pemfile::certs(&mut cert_reader)
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("Failed to parse certificate {}", cert_path.display()))?;
+    if certs.is_empty() {
+        bail!("No certificates found in {}", cert_path.display());
+    }
+
+    let mut key_reader = BufReader::new(
+        File::open(key_path).with_context(|| format!("Failed to open private key {}", key_path.display()))?,
+    );
+    let key = rustls_pemfile::private_key(&mut key_reader)
+        .with_context(|| format!("Failed to parse private key {}", key_path.display()))?
+        .ok_or_else(|| anyhow!("No private key found in {}", key_path.display()))?;
+
+    let signing_key = any_supported_type(&key).map_err(|e| anyhow!("Unsupported private key: {}", e))?;
+
+    Ok(CertifiedKey::new(certs, signing_key))
+}
+
+/// Serves the most recently loaded certificate for every handshake.
+#[derive(Debug)]
+struct ReloadingCertResolver {
+    current: RwLock<Arc<CertifiedKey>>,
+}
+
+impl ReloadingCertResolver {
+    fn new(key: CertifiedKey) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(key)),
+        }
+    }
+
+    fn replace(&self, key: CertifiedKey) {
+        *self.current.write().unwrap() = Arc::new(key);
+    }
+}
+
+impl ResolvesServerCert for ReloadingCertResolver {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        Some(self.current.read().unwrap().clone())
+    }
+}
+
+fn spawn_reloader(resolver: Arc<ReloadingCertResolver>, cert_path: PathBuf, key_path: PathBuf, interval: Duration) {
+    tokio::spawn(async move {
+        let mut last_modified = modified_times(&cert_path, &key_path);
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+
+            let modified = modified_times(&cert_path, &key_path);
+            if modified == last_modified {
+                continue;
+            }
+
+            // Keep serving the old certificate if the new files are incomplete or invalid;
+            // a later tick will pick them up once both have been written.
+            match load_certified_key(&cert_path, &key_path) {
+                Ok(key) => {
+                    resolver.replace(key);
+                    last_modified = modified;
+                    tracing::info!(cert_path = %cert_path.display(), "TLS certificate reloaded");
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        cert_path = %cert_path.display(),
+                        error = %e,
+                        "TLS certificate changed but could not be reloaded"
+                    );
+                }
+            }
+        }
+    });
+}
+
+fn modified_times(cert_path: &Path, key_path: &Path) -> (Option<SystemTime>, Option<SystemTime>) {
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    (modified(cert_path), modified(key_path))
+}
+
+/// Identity of a client that authenticated with a certificate during the TLS handshake.
+///
+/// Inserted into the request extensions by the connection handler; use
+/// `Option<ClientIdentity>` in handlers that also accept anonymous clients.
+#[derive(Debug, Clone)]
+pub struct ClientIdentity {
+    pub subject: String,
+    pub common_name: Option<String>,
+    /// Subject alternative names in OpenSSL notation, e.g. `DNS:billing.internal`.
+    pub sans: Vec<String>,
+}
+
+impl ClientIdentity {
+    /// Parses the leaf certificate presented by the client.
+    pub fn from_certificate(cert: &CertificateDer<'_>) -> Option<Self> {
+        use x509_parser::{extensions::GeneralName, prelude::*};
+
+        let (_, cert) = X509Certificate::from_der(cert.as_ref()).ok()?;
+
+        let common_name = cert
+            .subject()
+            .iter_common_name()
+            .next()
+            .and_then(|cn| cn.as_str().ok())
+            .map(str::to_string);
+
+        let sans = cert
+            .subject_alternative_name()
+            .ok()
+            .flatten()
+            .map(|ext| {
+                ext.value
+                    .general_names
+                    .iter()
+                    .filter_map(|name| match name {
+                        GeneralName::DNSName(dns) => Some(format!("DNS:{}", dns)),
+                        GeneralName::URI(uri) => Some(format!("URI:{}", uri)),
+                        GeneralName::RFC822Name(email) => Some(format!("email:{}", email)),
+                        GeneralName::IPAddress(bytes) => ip_from_bytes(bytes).map(|ip| format!("IP:{}", ip)),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Some(Self {
+            subject: cert.subject().to_string(),
+            common_name,
+            sans,
+        })
+    }
+
+    /// Returns true if `identity` names this client by subject, common name or SAN.
+    pub fn matches(&self, identity: &str) -> bool {
+        self.subject == identity
+            || self.common_name.as_deref() == Some(identity)
+            || self.sans.iter().any(|san| san == identity)
+    }
+}
+
+impl fmt::Display for ClientIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.sans.is_empty() {
+            write!(f, "{}", self.subject)
+        } else {
+            write!(f, "{} [{}]", self.subject, self.sans.join(", "))
+        }
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientIdentity
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<ClientIdentity>()
+            .cloned()
+            .ok_or_else(|| AppError::Forbidden("Client certificate required".to_string()))
+    }
+}
+
+fn ip_from_bytes(bytes: &[u8]) -> Option<std::net::IpAddr> {
+    match bytes.len() {
+        4 => <[u8; 4]>::try_from(bytes).ok().map(std::net::IpAddr::from),
+        16 => <[u8; 16]>::try_from(bytes).ok().map(std::net::IpAddr::from),
+        _ => None,
+    }
+}
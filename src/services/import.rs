@@ -0,0 +1,571 @@
+use crate::config::ImportConfig;
+use crate::domain::{
+    errors::{AppError, AppResult, FieldError},
+    links::ApiUrls,
+    requests::{CreateUserRequest, ImportMode, ImportUsersQuery, IMPORT_FIELDS},
+    responses::{ImportJobLinks, ImportJobResponse, ImportJobStatus, ImportRowError},
+    validation::Validate,
+};
+use crate::services::user::{ImportOutcome, UserService};
+use axum::body::Bytes;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, VecDeque};
+use crate::utils::sync::lock_recover;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tracing::Instrument;
+use uuid::Uuid;
+
+/// Upload formats accepted by the import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    Csv,
+    Ndjson,
+}
+
+/// A data row of an upload and the line it starts on.
+#[derive(Debug)]
+struct ImportRow {
+    line: u64,
+    request: Result<CreateUserRequest, Vec<FieldError>>,
+}
+
+#[derive(Debug)]
+struct ImportJob {
+    status: ImportJobResponse,
+    errors: Vec<ImportRowError>,
+}
+
+type JobHandle = Arc<Mutex<ImportJob>>;
+
+/// Runs user imports and keeps their progress and error reports for polling.
+#[derive(Debug)]
+pub struct ImportService {
+    users: Arc<UserService>,
+    config: ImportConfig,
+    urls: ApiUrls,
+    jobs: Mutex<VecDeque<JobHandle>>,
+}
+
+impl ImportService {
+    pub fn new(users: Arc<UserService>, config: ImportConfig, urls: ApiUrls) -> Self {
+        Self {
+            users,
+            config,
+            urls,
+            jobs: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Parses the upload and imports its rows. Uploads up to
+    /// `background_threshold_rows` rows are finished when this returns; larger
+    /// ones keep running in the background and return a queued job.
+    pub async fn submit(&self, format: ImportFormat, data: Bytes, query: ImportUsersQuery) -> AppResult<ImportJobResponse> {
+        query.validate().map_err(|errors| {
+            let error = AppError::Validation(errors);
+            tracing::warn!(error = %error, "User import validation failed");
+            error
+        })?;
+
+        let mapping_query = query.clone();
+        let rows = tokio::task::spawn_blocking(move || parse_rows(format, &data, &mapping_query))
+            .await
+            .map_err(|e| AppError::Internal(format!("Import parsing panicked: {}", e)))??;
+
+        let job = self.create_job(rows.len(), query.mode, query.dry_run)?;
+        let background = rows.len() > self.config.background_threshold_rows;
+
+        let users = self.users.clone();
+        let task_job = job.clone();
+        let task = tokio::spawn(
+            async move {
+                let run_job = task_job.clone();
+                let span = tracing::Span::current();
+                let result = tokio::task::spawn_blocking(move || {
+                    span.in_scope(|| run(&users, &run_job, rows, query.mode, query.dry_run))
+                })
+                .await;
+                if let Err(error) = result {
+                    tracing::error!(error = %error, "Import job panicked");
+                    finish(&task_job, Some("the import stopped unexpectedly".to_string()));
+                }
+            }
+            .instrument(tracing::Span::current()),
+        );
+
+        if !background {
+            task.await
+                .map_err(|e| AppError::Internal(format!("Import job panicked: {}", e)))?;
+        }
+        let status = lock(&job).status.clone();
+        Ok(status)
+    }
+
+    pub fn get(&self, id: Uuid) -> AppResult<ImportJobResponse> {
+        let job = self.find(id)?;
+        let status = lock(&job).status.clone();
+        Ok(status)
+    }
+
+    /// Errors of the rows rejected so far, in upload order.
+    pub fn errors(&self, id: Uuid) -> AppResult<Vec<ImportRowError>> {
+        let job = self.find(id)?;
+        let errors = lock(&job).errors.clone();
+        Ok(errors)
+    }
+
+    fn find(&self, id: Uuid) -> AppResult<JobHandle> {
+        lock(&self.jobs)
+            .iter()
+            .find(|job| lock(job).status.id == id)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound(format!("import job {}", id)))
+    }
+
+    /// Registers a queued job, dropping the oldest finished jobs beyond `max_jobs`.
+    /// Unfinished jobs are never dropped, so they also bound how many can run.
+    fn create_job(&self, total_rows: usize, mode: ImportMode, dry_run: bool) -> AppResult<JobHandle> {
+        let mut jobs = lock(&self.jobs);
+        while jobs.len() >= self.config.max_jobs.max(1) {
+            let finished = jobs
+                .iter()
+                .position(|job| lock(job).status.finished_at.is_some());
+            match finished {
+                Some(index) => {
+                    jobs.remove(index);
+                }
+                None => {
+                    return Err(AppError::ServiceUnavailable(
+                        "too many imports are running, try again later".to_string(),
+                    ))
+                }
+            }
+        }
+
+        let id = Uuid::new_v4();
+        let job = Arc::new(Mutex::new(ImportJob {
+            status: ImportJobResponse {
+                id,
+                status: ImportJobStatus::Queued,
+                dry_run,
+                mode,
+                total_rows,
+                processed_rows: 0,
+                created: 0,
+                updated: 0,
+                failed: 0,
+                error: None,
+                created_at: chrono::Utc::now().to_rfc3339(),
+                finished_at: None,
+                links: ImportJobLinks {
+                    self_link: self.urls.import_job(id),
+                    errors: self.urls.import_job_errors(id),
+                },
+            },
+            errors: Vec::new(),
+        }));
+        jobs.push_back(job.clone());
+        Ok(job)
+    }
+}
+
+/// Locks the job list or a job. A panicking import leaves at most one row
+/// half counted, so the state is still worth reporting.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    lock_recover(mutex, "Import job")
+}
+
+fn run(users: &UserService, job: &Mutex<ImportJob>, rows: Vec<ImportRow>, mode: ImportMode, dry_run: bool) {
+    let job_id = {
+        let mut job = lock(job);
+        job.status.status = ImportJobStatus::Running;
+        job.status.id
+    };
+    tracing::info!(%job_id, rows = rows.len(), ?mode, dry_run, "Import job started");
+
+    // Normalized email address -> line of the row that imported it
+    let mut imported: HashMap<String, u64> = HashMap::new();
+    for row in rows {
+        let result = match row.request {
+            Ok(request) => import_row(users, request, row.line, mode, dry_run, &mut imported),
+            Err(errors) => Ok(Err(errors)),
+        };
+
+        let mut state = lock(job);
+        match result {
+            Ok(Ok(ImportOutcome::Created)) => state.status.created += 1,
+            Ok(Ok(ImportOutcome::Updated)) => state.status.updated += 1,
+            Ok(Err(errors)) => {
+                state.status.failed += 1;
+                state.errors.extend(errors.into_iter().map(|error| ImportRowError {
+                    line: row.line,
+                    field: error.field,
+                    rule: error.rule,
+                    message: error.message,
+                }));
+            }
+            Err(error) => {
+                drop(state);
+                tracing::error!(%job_id, line = row.line, error = %error, "Import job failed");
+                finish(job, Some(error.to_string()));
+                return;
+            }
+        }
+        state.status.processed_rows += 1;
+    }
+
+    let status = finish(job, None);
+    tracing::info!(
+        %job_id,
+        created = status.created,
+        updated = status.updated,
+        failed = status.failed,
+        dry_run,
+        "Import job completed"
+    );
+}
+
+/// Marks the job completed, or failed with `error`, and returns its final status.
+fn finish(job: &Mutex<ImportJob>, error: Option<String>) -> ImportJobResponse {
+    let mut job = lock(job);
+    job.status.status = if error.is_some() {
+        ImportJobStatus::Failed
+    } else {
+        ImportJobStatus::Completed
+    };
+    job.status.error = error;
+    job.status.finished_at = Some(chrono::Utc::now().to_rfc3339());
+    job.status.clone()
+}
+
+/// Imports one valid row. Rejections are returned as field errors for the
+/// report; any other error stops the job.
+fn import_row(
+    users: &UserService,
+    request: CreateUserRequest,
+    line: u64,
+    mode: ImportMode,
+    dry_run: bool,
+    imported: &mut HashMap<String, u64>,
+) -> AppResult<Result<ImportOutcome, Vec<FieldError>>> {
+    let email = match users.normalize_email(&request.email) {
+        Ok(email) => email,
+        Err(AppError::Validation(errors)) => return Ok(Err(errors)),
+        Err(error) => return Err(error),
+    };
+    // Catches repeated addresses in a dry run too, where nothing is written
+    if let Some(first_line) = imported.get(&email) {
+        return Ok(Err(vec![FieldError::new(
+            "email",
+            "duplicate",
+            format!("repeats the address imported on line {}", first_line),
+        )]));
+    }
+
+    match users.import_user(request, &email, mode, dry_run) {
+        Ok(outcome) => {
+            imported.insert(email, line);
+            Ok(Ok(outcome))
+        }
+        Err(AppError::Validation(errors)) => Ok(Err(errors)),
+        Err(AppError::UserAlreadyExists(_)) => Ok(Err(vec![FieldError::new(
+            "email",
+            "unique",
+            "is already registered; import with mode=upsert to update the user",
+        )])),
+        Err(error) => Err(error),
+    }
+}
+
+/// Parses an upload into rows. Problems with single rows are kept for the
+/// error report; only an upload whose columns cannot be mapped is rejected.
+fn parse_rows(format: ImportFormat, data: &[u8], query: &ImportUsersQuery) -> AppResult<Vec<ImportRow>> {
+    let mapping = query.column_mapping();
+    match format {
+        ImportFormat::Csv => parse_csv(data, &mapping),
+        ImportFormat::Ndjson => Ok(parse_ndjson(data, &mapping)),
+    }
+}
+
+fn parse_csv(data: &[u8], mapping: &[(&str, &str)]) -> AppResult<Vec<ImportRow>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(data);
+    let headers = reader
+        .headers()
+        .map_err(|e| AppError::InvalidInput(format!("CSV header could not be read: {}", e)))?
+        .clone();
+
+    let mut columns: Vec<(usize, &'static str)> = Vec::new();
+    let mut errors = Vec::new();
+    for (index, header) in headers.iter().enumerate() {
+        let Some(field) = mapped_field(header, mapping) else {
+            continue;
+        };
+        if columns.iter().any(|&(_, f)| f == field) {
+            errors.push(FieldError::new(
+                "columns",
+                "column_mapping",
+                format!("more than one column maps to '{}'", field),
+            ));
+        }
+        columns.push((index, field));
+    }
+    for field in IMPORT_FIELDS {
+        if !columns.iter().any(|&(_, f)| f == field) {
+            errors.push(FieldError::new("columns", "required", format!("no column maps to '{}'", field)));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
+    }
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let row = match record {
+            Ok(record) if record.iter().all(str::is_empty) => continue,
+            Ok(record) => {
+                let fields = columns
+                    .iter()
+                    .filter_map(|&(index, field)| record.get(index).map(|value| (field.to_string(), Value::from(value))))
+                    .collect();
+                ImportRow {
+                    line: record.position().map_or(0, |p| record_line(data, p)),
+                    request: to_request(&fields),
+                }
+            }
+            Err(error) => ImportRow {
+                line: error.position().map_or(0, |p| record_line(data, p)),
+                request: Err(vec![FieldError::new("row", "parse", error.to_string())]),
+            },
+        };
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// The line a record starts on. The csv reader positions a record where the
+/// previous one ended, before the blank lines and the `\n` of a CRLF it skips.
+fn record_line(data: &[u8], position: &csv::Position) -> u64 {
+    let start = usize::try_from(position.byte()).map_or(data.len(), |byte| byte.min(data.len()));
+    let skipped = data[start..]
+        .iter()
+        .take_while(|&&byte| byte == b'\r' || byte == b'\n')
+        .filter(|&&byte| byte == b'\n')
+        .count();
+    position.line() + skipped as u64
+}
+
+fn parse_ndjson(data: &[u8], mapping: &[(&str, &str)]) -> Vec<ImportRow> {
+    data.split(|&byte| byte == b'\n')
+        .zip(1..)
+        .filter(|(line, _)| !line.trim_ascii().is_empty())
+        .map(|(line, number)| {
+            let request = match serde_json::from_slice(line) {
+                Ok(Value::Object(object)) => {
+                    let fields = object
+                        .into_iter()
+                        .filter_map(|(key, value)| mapped_field(&key, mapping).map(|field| (field.to_string(), value)))
+                        .collect();
+                    to_request(&fields)
+                }
+                Ok(_) => Err(vec![FieldError::new("row", "parse", "must be a JSON object")]),
+                Err(error) => Err(vec![FieldError::new("row", "parse", error.to_string())]),
+            };
+            ImportRow { line: number, request }
+        })
+        .collect()
+}
+
+/// The field a source column maps to: its entry in the mapping, or the field it
+/// is named after.
+fn mapped_field(column: &str, mapping: &[(&str, &str)]) -> Option<&'static str> {
+    let column = column.trim();
+    let field = mapping
+        .iter()
+        .find(|(source, _)| source.eq_ignore_ascii_case(column))
+        .map_or(column, |&(_, field)| field);
+    IMPORT_FIELDS.into_iter().find(|f| f.eq_ignore_ascii_case(field))
+}
+
+/// Builds the request from the mapped values, reporting missing and mistyped
+/// values along with the violated validation rules.
+fn to_request(fields: &Map<String, Value>) -> Result<CreateUserRequest, Vec<FieldError>> {
+    let mut errors = Vec::new();
+    let request = CreateUserRequest {
+        name: text_value(fields, "name", &mut errors),
+        email: text_value(fields, "email", &mut errors),
+        age: age_value(fields, &mut errors),
+    };
+
+    if let Err(rule_errors) = request.validate() {
+        // A mistyped value also fails its rules; report it once
+        let rule_errors: Vec<FieldError> = rule_errors
+            .into_iter()
+            .filter(|error| !errors.iter().any(|e: &FieldError| e.field == error.field))
+            .collect();
+        errors.extend(rule_errors);
+    }
+    if errors.is_empty() {
+        Ok(request)
+    } else {
+        errors.sort_by_key(|error| IMPORT_FIELDS.iter().position(|f| *f == error.field));
+        Err(errors)
+    }
+}
+
+fn text_value(fields: &Map<String, Value>, field: &str, errors: &mut Vec<FieldError>) -> String {
+    match fields.get(field) {
+        Some(Value::String(value)) => value.clone(),
+        // Left to the `required` rule
+        None | Some(Value::Null) => String::new(),
+        Some(_) => {
+            errors.push(FieldError::new(field, "type", "must be a string"));
+            String::new()
+        }
+    }
+}
+
+/// CSV cells hold ages as text, NDJSON rows as numbers; both are accepted.
+fn age_value(fields: &Map<String, Value>, errors: &mut Vec<FieldError>) -> u32 {
+    let age = match fields.get("age") {
+        None | Some(Value::Null) => {
+            errors.push(FieldError::new("age", "required", "must not be empty"));
+            return 0;
+        }
+        Some(Value::String(value)) if value.is_empty() => {
+            errors.push(FieldError::new("age", "required", "must not be empty"));
+            return 0;
+        }
+        Some(Value::String(value)) => value.parse().ok(),
+        Some(Value::Number(value)) => value.as_u64().and_then(|age| u32::try_from(age).ok()),
+        Some(_) => None,
+    };
+    age.unwrap_or_else(|| {
+        errors.push(FieldError::new("age", "type", "must be a whole number"));
+        0
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(row: &ImportRow) -> Vec<(&str, &str)> {
+        match &row.request {
+            Ok(_) => Vec::new(),
+            Err(errors) => errors.iter().map(|e| (e.field.as_str(), e.rule.as_str())).collect(),
+        }
+    }
+
+    fn validation_errors(result: AppResult<Vec<ImportRow>>) -> Vec<(String, String)> {
+        match result {
+            Err(AppError::Validation(errors)) => errors.into_iter().map(|e| (e.field, e.message)).collect(),
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn csv_rows_carry_the_line_they_start_on() {
+        let data = b"name,email,age\nJane,jane@example.com,32\n\n\"Multi\nLine\",multi@example.com,40\nBob,bob@example.com,41\n";
+
+        let rows = parse_csv(data, &[]).unwrap();
+
+        assert_eq!(rows.iter().map(|row| row.line).collect::<Vec<_>>(), [2, 4, 6]);
+        assert_eq!(rules(&rows[1]), [("name", "forbidden_character")]);
+    }
+
+    #[test]
+    fn csv_line_numbers_count_crlf_line_endings() {
+        let data = b"name,email,age\r\nJane,jane@example.com,32\r\n\r\nBob,bob@example.com,41\r\n";
+
+        let rows = parse_csv(data, &[]).unwrap();
+
+        assert_eq!(rows.iter().map(|row| row.line).collect::<Vec<_>>(), [2, 4]);
+    }
+
+    #[test]
+    fn csv_columns_are_mapped_by_name_or_mapping() {
+        let data = b"Full Name,Mail,AGE,notes\n Jane ,jane@example.com,32,ignored\n";
+
+        let rows = parse_csv(data, &[("full name", "name"), ("mail", "email")]).unwrap();
+
+        let request = rows[0].request.as_ref().unwrap();
+        assert_eq!(request.name, "Jane");
+        assert_eq!(request.email, "jane@example.com");
+        assert_eq!(request.age, 32);
+    }
+
+    #[test]
+    fn csv_without_a_column_for_every_field_is_rejected() {
+        let errors = validation_errors(parse_csv(b"name,mail\nJane,jane@example.com\n", &[]));
+
+        assert_eq!(
+            errors,
+            [
+                ("columns".to_string(), "no column maps to 'email'".to_string()),
+                ("columns".to_string(), "no column maps to 'age'".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn csv_with_two_columns_for_a_field_is_rejected() {
+        let errors = validation_errors(parse_csv(b"name,email,mail,age\n", &[("mail", "email")]));
+
+        assert_eq!(
+            errors,
+            [("columns".to_string(), "more than one column maps to 'email'".to_string())]
+        );
+    }
+
+    #[test]
+    fn csv_ages_must_be_whole_numbers() {
+        let rows = parse_csv(b"name,email,age\nJane,jane@example.com,thirty\nBob,bob@example.com,\n", &[]).unwrap();
+
+        assert_eq!(rules(&rows[0]), [("age", "type")]);
+        assert_eq!(rules(&rows[1]), [("age", "required")]);
+    }
+
+    #[test]
+    fn ndjson_rows_are_numbered_by_line_and_skip_blank_lines() {
+        let data = b"{\"name\":\"Jane\",\"email\":\"jane@example.com\",\"age\":32}\n\n[1,2]\n{broken\n";
+
+        let rows = parse_ndjson(data, &[]);
+
+        assert_eq!(rows.iter().map(|row| row.line).collect::<Vec<_>>(), [1, 3, 4]);
+        assert!(rows[0].request.is_ok());
+        assert_eq!(rules(&rows[1]), [("row", "parse")]);
+        assert_eq!(rules(&rows[2]), [("row", "parse")]);
+    }
+
+    #[test]
+    fn ndjson_keys_are_mapped_and_unknown_keys_ignored() {
+        let data = b"{\"full_name\":\"Jane\",\"email\":\"jane@example.com\",\"age\":\"32\",\"id\":7}\n";
+
+        let rows = parse_ndjson(data, &[("full_name", "name")]);
+
+        let request = rows[0].request.as_ref().unwrap();
+        assert_eq!((request.name.as_str(), request.age), ("Jane", 32));
+    }
+
+    #[test]
+    fn to_request_reports_type_errors_once_in_field_order() {
+        let fields = serde_json::json!({ "name": 42, "email": "not-an-email", "age": -1 });
+
+        let errors = to_request(fields.as_object().unwrap()).unwrap_err();
+
+        let errors: Vec<_> = errors.iter().map(|e| (e.field.as_str(), e.rule.as_str())).collect();
+        assert_eq!(errors, [("name", "type"), ("email", "email"), ("age", "type")]);
+    }
+
+    #[test]
+    fn to_request_applies_the_validation_rules() {
+        let fields = serde_json::json!({ "name": "Jane", "email": "jane@example.com", "age": 151 });
+
+        let errors = to_request(fields.as_object().unwrap()).unwrap_err();
+
+        assert_eq!(errors[0].field, "age");
+        assert_eq!(errors[0].rule, "max");
+    }
+}
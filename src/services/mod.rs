@@ -1 +1,2 @@
+pub mod import;
 pub mod user;
\ No newline at end of file
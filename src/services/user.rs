@@ -1,22 +1,44 @@
 use crate::domain::{
     entities::User,
-    errors::{AppError, AppResult},
-    requests::{CreateUserRequest, ListUsersQuery, UpdateUserRequest},
-    responses::{UserProfileResponse, UserResponse, UsersListResponse},
+    errors::{AppError, AppResult, FieldError},
+    links::ApiUrls,
+    requests::{CreateUserRequest, GetUserQuery, ImportMode, ListUsersQuery, UpdateUserRequest},
+    responses::{PageLinks, UserProfileResponse, UserResponse, UsersListResponse},
+    validation::Validate,
 };
 use crate::repositories::user::UserRepository;
+use crate::telemetry::redaction;
+use crate::utils::{
+    email::{DomainBlocklist, EmailAddress},
+    text,
+};
+use icu_collator::{Collator, CollatorOptions};
+use icu_locid::Locale;
 use std::sync::Arc;
 use tracing;
 use uuid::Uuid;
 
+/// What importing a row did, or would do in a dry run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportOutcome {
+    Created,
+    Updated,
+}
+
 #[derive(Debug)]
 pub struct UserService {
     repository: Arc<UserRepository>,
+    email_blocklist: DomainBlocklist,
+    urls: ApiUrls,
 }
 
 impl UserService {
-    pub fn new(repository: Arc<UserRepository>) -> Self {
-        Self { repository }
+    pub fn new(repository: Arc<UserRepository>, email_blocklist: DomainBlocklist, urls: ApiUrls) -> Self {
+        Self {
+            repository,
+            email_blocklist,
+            urls,
+        }
     }
 
     pub fn create_user(&self, request: CreateUserRequest) -> AppResult<UserResponse> {
@@ -24,41 +46,45 @@ impl UserService {
         
         // Validate request
         request.validate()
-            .map_err(|e| {
-                tracing::warn!(error = %e, "User creation validation failed");
-                AppError::InvalidInput(e)
+            .map_err(|errors| {
+                let error = AppError::Validation(errors);
+                tracing::warn!(error = %error, "User creation validation failed");
+                error
             })?;
 
+        let email = self.normalize_email(&request.email)?;
+
         // Create user entity
-        let user = User::new(request.name.trim().to_string(), request.email.trim().to_lowercase(), request.age);
+        let user = User::new(text::normalize(&request.name), email, request.age);
 
-        tracing::debug!(user_id = %user.id, email = %user.email, "User entity created");
+        tracing::debug!(user_id = %user.id, email = redaction::email(&user.email), "User entity created");
 
         // Save to repository
         let created_user = self.repository.create(user)?;
 
         tracing::info!(
             user_id = %created_user.id,
-            user_name = %created_user.name,
-            user_email = %created_user.email,
+            user_name = redaction::pii(&created_user.name),
+            user_email = redaction::email(&created_user.email),
             "User created successfully"
         );
 
-        Ok(UserResponse::from(created_user))
+        Ok(UserResponse::new(created_user, &self.urls))
     }
 
-    pub fn get_user(&self, id: Uuid) -> AppResult<UserResponse> {
+    pub fn get_user(&self, id: Uuid, query: GetUserQuery) -> AppResult<UserResponse> {
         tracing::debug!(user_id = %id, "Fetching user");
+        let view = query.view()?;
         
         let user = self.repository.find_by_id(id)?;
         
         tracing::info!(
             user_id = %user.id,
-            user_name = %user.name,
+            user_name = redaction::pii(&user.name),
             "User retrieved successfully"
         );
         
-        Ok(UserResponse::from(user))
+        Ok(UserResponse::render(user, &view, &self.urls))
     }
 
     pub fn get_user_profile(&self, id: Uuid) -> AppResult<UserProfileResponse> {
@@ -68,11 +94,11 @@ impl UserService {
         
         tracing::info!(
             user_id = %user.id,
-            user_name = %user.name,
+            user_name = redaction::pii(&user.name),
             "User profile retrieved successfully"
         );
         
-        Ok(UserProfileResponse::from(user))
+        Ok(UserProfileResponse::new(user, &self.urls))
     }
 
     pub fn list_users(&self, query: ListUsersQuery) -> AppResult<UsersListResponse> {
@@ -84,13 +110,15 @@ impl UserService {
         
         // Validate query
         query.validate()
-            .map_err(|e| {
-                tracing::warn!(error = %e, "User list validation failed");
-                AppError::InvalidInput(e)
+            .map_err(|errors| {
+                let error = AppError::Validation(errors);
+                tracing::warn!(error = %error, "User list validation failed");
+                error
             })?;
+        let view = query.view()?;
 
         // Get all users
-        let all_users = self.repository.find_all()?;
+        let all_users = self.sorted_users(query.locale.as_deref())?;
         let total = all_users.len();
 
         // Apply pagination
@@ -101,7 +129,7 @@ impl UserService {
             .into_iter()
             .skip(offset)
             .take(limit)
-            .map(UserResponse::from)
+            .map(|user| UserResponse::render(user, &view, &self.urls))
             .collect();
 
         tracing::info!(
@@ -113,6 +141,7 @@ impl UserService {
         );
 
         Ok(UsersListResponse {
+            links: PageLinks::new(&self.urls, &query, total),
             users,
             total,
             limit,
@@ -120,14 +149,40 @@ impl UserService {
         })
     }
 
+    /// The next `limit` users of an export in id order, after the last id of the
+    /// previous page, together with the last id of this page; `None` once every
+    /// user was exported. Pages are read from the store one at a time, so an
+    /// export never holds more than one page.
+    pub fn export_page(
+        &self,
+        after: Option<Uuid>,
+        limit: usize,
+    ) -> AppResult<(Vec<UserResponse>, Option<Uuid>)> {
+        let users = self.repository.find_page_after(after, limit)?;
+        let last = users.last().map(|user| user.id);
+        let users = users
+            .into_iter()
+            .map(|user| UserResponse::new(user, &self.urls))
+            .collect();
+        Ok((users, last))
+    }
+
+    fn sorted_users(&self, locale: Option<&str>) -> AppResult<Vec<User>> {
+        let mut users = self.repository.find_all()?;
+        let collator = collator(locale)?;
+        users.sort_by(|a, b| collator.compare(&a.name, &b.name).then_with(|| a.id.cmp(&b.id)));
+        Ok(users)
+    }
+
     pub fn update_user(&self, id: Uuid, request: UpdateUserRequest) -> AppResult<UserResponse> {
         tracing::info!(user_id = %id, "Updating user");
         
         // Validate request
         request.validate()
-            .map_err(|e| {
-                tracing::warn!(user_id = %id, error = %e, "User update validation failed");
-                AppError::InvalidInput(e)
+            .map_err(|errors| {
+                let error = AppError::Validation(errors);
+                tracing::warn!(user_id = %id, error = %error, "User update validation failed");
+                error
             })?;
 
         if !request.has_updates() {
@@ -143,10 +198,10 @@ impl UserService {
 
         // Apply updates
         if let Some(name) = request.name {
-            user.update_name(name.trim().to_string());
+            user.update_name(text::normalize(&name));
         }
         if let Some(email) = request.email {
-            user.update_email(email.trim().to_lowercase());
+            user.update_email(self.normalize_email(&email)?);
         }
         if let Some(age) = request.age {
             user.update_age(age);
@@ -157,15 +212,70 @@ impl UserService {
 
         tracing::info!(
             user_id = %updated_user.id,
-            user_name = %updated_user.name,
-            user_email = %updated_user.email,
-            original_name = %original_name,
-            original_email = %original_email,
+            user_name = redaction::pii(&updated_user.name),
+            user_email = redaction::email(&updated_user.email),
+            original_name = redaction::pii(&original_name),
+            original_email = redaction::email(&original_email),
             original_age = original_age,
             "User updated successfully"
         );
 
-        Ok(UserResponse::from(updated_user))
+        Ok(UserResponse::new(updated_user, &self.urls))
+    }
+
+    /// Imports one row: creates the user, or in upsert mode updates the name and
+    /// age of the user registered with the same email address. A dry run
+    /// performs every check but writes nothing.
+    ///
+    /// `email` is the address of the request as returned by `normalize_email`.
+    pub fn import_user(
+        &self,
+        request: CreateUserRequest,
+        email: &str,
+        mode: ImportMode,
+        dry_run: bool,
+    ) -> AppResult<ImportOutcome> {
+        request.validate().map_err(AppError::Validation)?;
+        let email = email.to_string();
+        let name = text::normalize(&request.name);
+
+        match self.repository.find_by_email(&email)? {
+            Some(mut user) if mode == ImportMode::Upsert => {
+                user.update_name(name);
+                user.update_age(request.age);
+                if !dry_run {
+                    self.repository.update(user.id, user.clone())?;
+                }
+                tracing::debug!(user_id = %user.id, dry_run, "Imported row updated user");
+                Ok(ImportOutcome::Updated)
+            }
+            Some(_) => Err(AppError::UserAlreadyExists(email)),
+            None => {
+                let user = User::new(name, email, request.age);
+                if !dry_run {
+                    self.repository.create(user.clone())?;
+                }
+                tracing::debug!(user_id = %user.id, dry_run, "Imported row created user");
+                Ok(ImportOutcome::Created)
+            }
+        }
+    }
+
+    /// Lowercases the domain of an address, keeping the case-sensitive local
+    /// part, and rejects disposable email providers.
+    pub fn normalize_email(&self, email: &str) -> AppResult<String> {
+        let invalid = |rule: &str, message: String| AppError::Validation(vec![FieldError::new("email", rule, message)]);
+
+        let address = EmailAddress::parse(email).map_err(|e| invalid("email", e.to_string()))?;
+        if self.email_blocklist.contains(&address) {
+            tracing::warn!(email_domain = %address.domain(), "Rejected disposable email address");
+            return Err(invalid(
+                "disposable_email",
+                "addresses of disposable email providers are not accepted".to_string(),
+            ));
+        }
+
+        Ok(address.to_string())
     }
 
     pub fn delete_user(&self, id: Uuid) -> AppResult<()> {
@@ -179,11 +289,24 @@ impl UserService {
 
         tracing::info!(
             user_id = %user.id,
-            user_name = %user.name,
-            user_email = %user.email,
+            user_name = redaction::pii(&user.name),
+            user_email = redaction::email(&user.email),
             "User deleted successfully"
         );
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Collation for `locale`, falling back to its closest supported parent; without
+/// a locale the language-neutral Unicode collation order is used.
+fn collator(locale: Option<&str>) -> AppResult<Collator> {
+    let locale: Locale = match locale {
+        Some(tag) => tag
+            .parse()
+            .map_err(|_| AppError::InvalidInput(format!("Invalid locale: {}", tag)))?,
+        None => Locale::UND,
+    };
+    Collator::try_new(&(&locale).into(), CollatorOptions::new())
+        .map_err(|e| AppError::Internal(format!("Failed to create collator for {}: {}", locale, e)))
+}
@@ -0,0 +1,290 @@
+use crate::config::AccessLogConfig;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError},
+    thread,
+};
+
+/// Fields that may be listed in `access_log.json_fields`.
+pub const JSON_FIELDS: &[&str] = &[
+    "timestamp",
+    "remote_addr",
+    "method",
+    "target",
+    "protocol",
+    "status",
+    "bytes",
+    "duration_ms",
+    "referer",
+    "user_agent",
+    "correlation_id",
+];
+
+// Lines buffered between the request path and the writer thread before new lines are dropped
+const QUEUE_CAPACITY: usize = 8192;
+
+/// One completed request, as recorded by the access log middleware.
+#[derive(Debug, Clone)]
+pub struct AccessLogEntry {
+    pub timestamp: DateTime<Utc>,
+    pub remote_addr: Option<String>,
+    pub method: String,
+    pub target: String,
+    pub protocol: String,
+    pub status: u16,
+    pub bytes: Option<u64>,
+    pub duration_ms: u128,
+    pub referer: Option<String>,
+    pub user_agent: Option<String>,
+    pub correlation_id: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+enum AccessLogFormat {
+    Common,
+    Combined,
+    Json(Vec<String>),
+}
+
+impl AccessLogFormat {
+    fn from_config(config: &AccessLogConfig) -> Result<Self> {
+        match config.format.as_str() {
+            "common" => Ok(Self::Common),
+            "combined" => Ok(Self::Combined),
+            "json" => {
+                if let Some(unknown) = config.json_fields.iter().find(|f| !JSON_FIELDS.contains(&f.as_str())) {
+                    bail!("Unknown access log field: {}", unknown);
+                }
+                Ok(Self::Json(config.json_fields.clone()))
+            }
+            other => bail!("Unsupported access log format: {}", other),
+        }
+    }
+
+    fn format(&self, entry: &AccessLogEntry) -> String {
+        match self {
+            Self::Common => common_line(entry),
+            Self::Combined => format!(
+                "{} \"{}\" \"{}\"",
+                common_line(entry),
+                escape(entry.referer.as_deref().unwrap_or("-")),
+                escape(entry.user_agent.as_deref().unwrap_or("-")),
+            ),
+            // Written by hand so the configured field order is preserved
+            Self::Json(fields) => {
+                let members: Vec<String> = fields
+                    .iter()
+                    .map(|field| format!("{}:{}", Value::from(field.as_str()), json_field(entry, field)))
+                    .collect();
+                format!("{{{}}}", members.join(","))
+            }
+        }
+    }
+}
+
+fn common_line(entry: &AccessLogEntry) -> String {
+    format!(
+        "{} - - [{}] \"{} {} {}\" {} {}",
+        entry.remote_addr.as_deref().unwrap_or("-"),
+        entry.timestamp.format("%d/%b/%Y:%H:%M:%S %z"),
+        entry.method,
+        escape(&entry.target),
+        entry.protocol,
+        entry.status,
+        entry.bytes.map_or_else(|| "-".to_string(), |b| b.to_string()),
+    )
+}
+
+fn json_field(entry: &AccessLogEntry, field: &str) -> Value {
+    let optional = |value: &Option<String>| value.clone().map_or(Value::Null, Value::String);
+    match field {
+        "timestamp" => Value::String(entry.timestamp.to_rfc3339()),
+        "remote_addr" => optional(&entry.remote_addr),
+        "method" => Value::String(entry.method.clone()),
+        "target" => Value::String(entry.target.clone()),
+        "protocol" => Value::String(entry.protocol.clone()),
+        "status" => Value::from(entry.status),
+        "bytes" => entry.bytes.map_or(Value::Null, Value::from),
+        "duration_ms" => Value::from(entry.duration_ms as u64),
+        "referer" => optional(&entry.referer),
+        "user_agent" => optional(&entry.user_agent),
+        "correlation_id" => optional(&entry.correlation_id),
+        _ => Value::Null,
+    }
+}
+
+// Keep quoted fields on one line and unambiguous for log parsers
+fn escape(value: &str) -> String {
+    value.escape_default().to_string()
+}
+
+/// Hands formatted access log lines to a dedicated writer thread so that
+/// file I/O never blocks request handling.
+#[derive(Debug, Clone)]
+pub struct AccessLogger {
+    format: AccessLogFormat,
+    sender: SyncSender<String>,
+}
+
+impl AccessLogger {
+    pub fn new(config: &AccessLogConfig) -> Result<Self> {
+        let format = AccessLogFormat::from_config(config)?;
+        let file = RotatingFile::open(config)?;
+
+        let (sender, receiver) = sync_channel(QUEUE_CAPACITY);
+        thread::Builder::new()
+            .name("access-log".to_string())
+            .spawn(move || write_lines(receiver, file))
+            .context("Failed to start access log writer")?;
+
+        Ok(Self { format, sender })
+    }
+
+    pub fn log(&self, entry: &AccessLogEntry) {
+        match self.sender.try_send(self.format.format(entry)) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => tracing::warn!("Access log queue full, dropping entry"),
+            Err(TrySendError::Disconnected(_)) => tracing::error!("Access log writer stopped"),
+        }
+    }
+}
+
+fn write_lines(receiver: Receiver<String>, mut file: RotatingFile) {
+    while let Ok(line) = receiver.recv() {
+        file.write_line(&line);
+        // Batch whatever else is queued before flushing
+        while let Ok(line) = receiver.try_recv() {
+            file.write_line(&line);
+        }
+        file.flush();
+    }
+}
+
+/// An append-only file that is rotated when it exceeds a size limit or when
+/// the rotation period changes, keeping a bounded number of old files.
+struct RotatingFile {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    size: u64,
+    period: Option<String>,
+    rotation: String,
+    max_size_bytes: u64,
+    max_files: usize,
+}
+
+impl RotatingFile {
+    fn open(config: &AccessLogConfig) -> Result<Self> {
+        if !matches!(config.rotation.as_str(), "hourly" | "daily" | "never") {
+            bail!("Unsupported access log rotation: {}", config.rotation);
+        }
+
+        let path = PathBuf::from(&config.path);
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+
+        let file = open_append(&path)?;
+        let metadata = file.metadata()?;
+        // Attribute an existing file to the period it was last written in, so a
+        // file left over from a previous day is rotated on the first write
+        let last_written = metadata.modified().map(DateTime::<Utc>::from).unwrap_or_else(|_| Utc::now());
+
+        Ok(Self {
+            writer: BufWriter::new(file),
+            size: metadata.len(),
+            period: period(&config.rotation, last_written),
+            rotation: config.rotation.clone(),
+            max_size_bytes: config.max_size_mb * 1024 * 1024,
+            max_files: config.max_files,
+            path,
+        })
+    }
+
+    fn write_line(&mut self, line: &str) {
+        let line_len = line.len() as u64 + 1;
+        let period_changed = period(&self.rotation, Utc::now()) != self.period;
+        let too_large = self.max_size_bytes > 0 && self.size > 0 && self.size + line_len > self.max_size_bytes;
+
+        if period_changed || too_large {
+            if let Err(e) = self.rotate() {
+                tracing::error!(error = %e, path = %self.path.display(), "Failed to rotate access log");
+            }
+        }
+
+        if let Err(e) = writeln!(self.writer, "{}", line) {
+            tracing::error!(error = %e, path = %self.path.display(), "Failed to write access log");
+            return;
+        }
+        self.size += line_len;
+    }
+
+    fn flush(&mut self) {
+        if let Err(e) = self.writer.flush() {
+            tracing::error!(error = %e, path = %self.path.display(), "Failed to flush access log");
+        }
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        self.writer.flush()?;
+
+        let rotated = PathBuf::from(format!(
+            "{}.{}",
+            self.path.display(),
+            Utc::now().format("%Y%m%dT%H%M%S%.3f")
+        ));
+        fs::rename(&self.path, &rotated)?;
+
+        self.writer = BufWriter::new(open_append(&self.path)?);
+        self.size = 0;
+        self.period = period(&self.rotation, Utc::now());
+
+        self.remove_expired()
+    }
+
+    /// Deletes the oldest rotated files beyond `max_files`.
+    fn remove_expired(&self) -> Result<()> {
+        let Some(file_name) = self.path.file_name().and_then(|n| n.to_str()) else {
+            return Ok(());
+        };
+        let dir = match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let prefix = format!("{}.", file_name);
+
+        // Rotated suffixes are timestamps, so lexical order is chronological
+        let mut rotated: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_str().is_some_and(|name| name.starts_with(&prefix)))
+            .map(|entry| entry.path())
+            .collect();
+        rotated.sort();
+
+        let excess = rotated.len().saturating_sub(self.max_files);
+        for path in rotated.into_iter().take(excess) {
+            fs::remove_file(&path)?;
+        }
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open access log {}", path.display()))
+}
+
+fn period(rotation: &str, now: DateTime<Utc>) -> Option<String> {
+    match rotation {
+        "hourly" => Some(now.format("%Y%m%d%H").to_string()),
+        "daily" => Some(now.format("%Y%m%d").to_string()),
+        _ => None,
+    }
+}
@@ -0,0 +1,319 @@
+use crate::config::CaptureConfig;
+use crate::telemetry::redaction;
+use crate::utils::{network::path_has_prefix, sync::lock_recover};
+use axum::http::{HeaderMap, Method};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+use uuid::Uuid;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// A recorded request/response exchange.
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureEntry {
+    pub id: Uuid,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u128,
+    pub correlation_id: Option<String>,
+    pub method: String,
+    /// Scheme and host the client used, after applying trusted proxy headers.
+    pub scheme: String,
+    pub host: Option<String>,
+    /// Path and query, with PII query parameters redacted.
+    pub uri: String,
+    pub version: String,
+    pub request_headers: Vec<CapturedHeader>,
+    pub request_body: CapturedBody,
+    pub status: Option<u16>,
+    pub response_headers: Vec<CapturedHeader>,
+    pub response_body: CapturedBody,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CapturedHeader {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CapturedBody {
+    /// Total size seen on the wire, including bytes beyond the capture limit.
+    pub size: usize,
+    pub truncated: bool,
+    pub content_type: Option<String>,
+    /// Body text after redaction; `None` for empty, binary or unredactable bodies.
+    pub text: Option<String>,
+}
+
+/// Decides what gets captured and holds the most recent entries in a bounded ring buffer.
+#[derive(Debug)]
+pub struct CaptureStore {
+    config: CaptureConfig,
+    entries: Mutex<VecDeque<CaptureEntry>>,
+}
+
+impl CaptureStore {
+    pub fn new(config: CaptureConfig) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(config.buffer_size)),
+            config,
+        }
+    }
+
+    pub fn max_body_bytes(&self) -> usize {
+        self.config.max_body_bytes
+    }
+
+    /// Returns true if a request to `path` should be captured, applying the
+    /// sampling rate of the first matching route.
+    pub fn should_capture(&self, method: &Method, path: &str) -> bool {
+        if !self.config.enabled || self.config.buffer_size == 0 {
+            return false;
+        }
+
+        self.config
+            .routes
+            .iter()
+            .find(|route| {
+                path_has_prefix(path, &route.path_prefix)
+                    && (route.methods.is_empty() || route.methods.iter().any(|m| m.eq_ignore_ascii_case(method.as_str())))
+            })
+            .is_some_and(|route| {
+                let rate = route.sample_rate.unwrap_or(self.config.sample_rate);
+                rate >= 1.0 || rand::random::<f64>() < rate
+            })
+    }
+
+    pub fn headers(&self, headers: &HeaderMap) -> Vec<CapturedHeader> {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let redact = self
+                    .config
+                    .redact_headers
+                    .iter()
+                    .any(|h| h.eq_ignore_ascii_case(name.as_str()));
+                CapturedHeader {
+                    name: name.to_string(),
+                    value: if redact {
+                        REDACTED.to_string()
+                    } else {
+                        String::from_utf8_lossy(value.as_bytes()).into_owned()
+                    },
+                }
+            })
+            .collect()
+    }
+
+    /// Converts captured bytes into a body record, redacting configured JSON
+    /// fields and, following the logging redaction policy, PII form parameters.
+    /// Bodies that cannot be redacted are only kept when redaction is off.
+    pub fn body(&self, bytes: &[u8], size: usize, content_type: Option<String>) -> CapturedBody {
+        let truncated = size > bytes.len();
+        let content_type_is = |name: &str| content_type.as_deref().is_some_and(|ct| ct.contains(name));
+
+        let text = if bytes.is_empty() {
+            None
+        } else if content_type_is("ndjson") {
+            // Every line has to parse; a truncated last line could leak fields
+            std::str::from_utf8(bytes).ok().and_then(|text| {
+                text.lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(|line| self.redacted_json(line.as_bytes()))
+                    .collect::<Option<Vec<_>>>()
+                    .map(|lines| lines.join("\n"))
+            })
+        } else if content_type_is("json") {
+            // Redaction needs the whole document; a truncated JSON body could leak fields
+            self.redacted_json(bytes)
+        } else if content_type_is("x-www-form-urlencoded") {
+            std::str::from_utf8(bytes).ok().map(redaction::form)
+        } else if redaction::enabled() {
+            // CSV exports, plain text and the like may hold PII we cannot find
+            None
+        } else {
+            std::str::from_utf8(bytes).ok().map(str::to_string)
+        };
+
+        CapturedBody {
+            size,
+            truncated,
+            content_type,
+            text,
+        }
+    }
+
+    fn redacted_json(&self, bytes: &[u8]) -> Option<String> {
+        serde_json::from_slice::<Value>(bytes).ok().map(|mut value| {
+            self.redact_json(&mut value);
+            value.to_string()
+        })
+    }
+
+    fn redact_json(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, field) in map.iter_mut() {
+                    if self.config.redact_json_fields.iter().any(|f| f.eq_ignore_ascii_case(key)) {
+                        *field = Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact_json(field);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact_json(item)),
+            _ => {}
+        }
+    }
+
+    pub fn push(&self, entry: CaptureEntry) {
+        if self.config.buffer_size == 0 {
+            return;
+        }
+        let mut entries = self.entries();
+        if entries.len() >= self.config.buffer_size {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Returns the captured entries, newest first.
+    pub fn list(&self) -> Vec<CaptureEntry> {
+        self.entries().iter().rev().cloned().collect()
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<CaptureEntry> {
+        self.entries().iter().find(|e| e.id == id).cloned()
+    }
+
+    pub fn clear(&self) {
+        self.entries().clear();
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, VecDeque<CaptureEntry>> {
+        lock_recover(&self.entries, "Capture buffer")
+    }
+
+    /// Exports the captured entries, oldest first, as an HTTP Archive (HAR 1.2) document.
+    pub fn to_har(&self) -> Value {
+        let entries: Vec<Value> = self.entries().iter().map(har_entry).collect();
+
+        json!({
+            "log": {
+                "version": "1.2",
+                "creator": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+                "entries": entries,
+            }
+        })
+    }
+}
+
+fn har_entry(entry: &CaptureEntry) -> Value {
+    let headers = |headers: &[CapturedHeader]| -> Vec<Value> {
+        headers
+            .iter()
+            .map(|h| json!({ "name": h.name, "value": h.value }))
+            .collect()
+    };
+    let query_string: Vec<Value> = entry
+        .uri
+        .split_once('?')
+        .map(|(_, query)| {
+            query
+                .split('&')
+                .map(|pair| {
+                    let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                    json!({ "name": name, "value": value })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let status = entry.status.unwrap_or(0);
+
+    // HAR requires an absolute URL
+    let url = match &entry.host {
+        Some(host) => format!("{}://{}{}", entry.scheme, host, entry.uri),
+        None => entry.uri.clone(),
+    };
+
+    let mut request = json!({
+        "method": entry.method,
+        "url": url,
+        "httpVersion": entry.version,
+        "cookies": [],
+        "headers": headers(&entry.request_headers),
+        "queryString": query_string,
+        "headersSize": -1,
+        "bodySize": entry.request_body.size,
+    });
+    if let Some(text) = &entry.request_body.text {
+        request["postData"] = json!({
+            "mimeType": entry.request_body.content_type.clone().unwrap_or_default(),
+            "text": text,
+        });
+    }
+
+    json!({
+        "startedDateTime": entry.started_at.to_rfc3339(),
+        "time": entry.duration_ms,
+        "request": request,
+        "response": {
+            "status": status,
+            "statusText": axum::http::StatusCode::from_u16(status)
+                .ok()
+                .and_then(|s| s.canonical_reason())
+                .unwrap_or(""),
+            "httpVersion": entry.version,
+            "cookies": [],
+            "headers": headers(&entry.response_headers),
+            "content": {
+                "size": entry.response_body.size,
+                "mimeType": entry.response_body.content_type.clone().unwrap_or_default(),
+                "text": entry.response_body.text.clone().unwrap_or_default(),
+            },
+            "redirectURL": "",
+            "headersSize": -1,
+            "bodySize": entry.response_body.size,
+        },
+        "cache": {},
+        "timings": {
+            "send": 0,
+            "wait": entry.duration_ms,
+            "receive": 0,
+        },
+        "comment": entry.correlation_id.clone().unwrap_or_default(),
+    })
+}
+
+/// Shared, append-only buffer for one side of a captured exchange.
+#[derive(Debug, Default)]
+pub struct BodyRecorder {
+    bytes: Vec<u8>,
+    size: usize,
+}
+
+impl BodyRecorder {
+    pub fn record(&mut self, chunk: &[u8], limit: usize) {
+        let remaining = limit.saturating_sub(self.bytes.len());
+        self.bytes.extend_from_slice(&chunk[..chunk.len().min(remaining)]);
+        self.size += chunk.len();
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+pub type SharedRecorder = Arc<Mutex<BodyRecorder>>;
@@ -0,0 +1,98 @@
+use crate::domain::errors::{AppError, AppResult};
+use chrono::{DateTime, Utc};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// Changes the active `EnvFilter` at runtime, optionally reverting to the
+/// startup directives after a delay.
+#[derive(Debug)]
+pub struct LogLevelController {
+    handle: reload::Handle<EnvFilter, Registry>,
+    default_directives: String,
+    revert_at: Mutex<Option<DateTime<Utc>>>,
+    // Bumped on every change so that a pending revert for an older change is ignored
+    generation: AtomicU64,
+}
+
+impl LogLevelController {
+    pub fn new(handle: reload::Handle<EnvFilter, Registry>, default_directives: String) -> Self {
+        Self {
+            handle,
+            default_directives,
+            revert_at: Mutex::new(None),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    pub fn current_directives(&self) -> String {
+        self.handle
+            .with_current(|filter| filter.to_string())
+            .unwrap_or_else(|_| self.default_directives.clone())
+    }
+
+    pub fn default_directives(&self) -> &str {
+        &self.default_directives
+    }
+
+    pub fn revert_at(&self) -> Option<DateTime<Utc>> {
+        *self.revert_at.lock().unwrap()
+    }
+
+    /// Replaces the filter directives, scheduling a revert to the defaults if
+    /// `revert_after` is given.
+    pub fn set(self: &Arc<Self>, directives: &str, revert_after: Option<Duration>) -> AppResult<()> {
+        let filter = EnvFilter::try_new(directives)
+            .map_err(|e| AppError::InvalidInput(format!("Invalid filter directives: {}", e)))?;
+
+        self.reload(filter)?;
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+
+        tracing::warn!(
+            directives = %directives,
+            revert_after_secs = revert_after.map(|d| d.as_secs()),
+            "Log filter changed"
+        );
+
+        let revert_at = revert_after.map(|delay| {
+            let controller = Arc::clone(self);
+            tokio::spawn(async move {
+                tokio::time::sleep(delay).await;
+                if controller.generation.load(Ordering::SeqCst) == generation {
+                    if let Err(e) = controller.reset() {
+                        tracing::error!(error = %e, "Failed to revert log filter");
+                    }
+                }
+            });
+            Utc::now() + chrono::Duration::from_std(delay).unwrap_or(chrono::Duration::zero())
+        });
+        *self.revert_at.lock().unwrap() = revert_at;
+
+        Ok(())
+    }
+
+    /// Restores the directives the process was started with.
+    pub fn reset(&self) -> AppResult<()> {
+        let filter = EnvFilter::try_new(&self.default_directives)
+            .map_err(|e| AppError::Internal(format!("Invalid default filter directives: {}", e)))?;
+
+        self.reload(filter)?;
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        *self.revert_at.lock().unwrap() = None;
+
+        tracing::warn!(directives = %self.default_directives, "Log filter reset to defaults");
+
+        Ok(())
+    }
+
+    fn reload(&self, filter: EnvFilter) -> AppResult<()> {
+        self.handle
+            .reload(filter)
+            .map_err(|e| AppError::Internal(format!("Failed to reload log filter: {}", e)))
+    }
+}
@@ -0,0 +1,4 @@
+pub mod access_log;
+pub mod capture;
+pub mod log_level;
+pub mod redaction;
@@ -0,0 +1,175 @@
+use crate::config::RedactionConfig;
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+use tracing::field::{display, DisplayValue};
+
+/// How values tagged as PII are written to logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedactionMode {
+    /// Log the value unchanged.
+    Off,
+    /// Replace the value with a truncated, salted SHA-256 digest so that
+    /// occurrences of the same value can still be correlated.
+    Hash,
+    /// Keep only the first character (and the domain of email addresses).
+    Mask,
+    /// Omit the field entirely.
+    Drop,
+}
+
+impl std::str::FromStr for RedactionMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Self::Off),
+            "hash" => Ok(Self::Hash),
+            "mask" => Ok(Self::Mask),
+            "drop" => Ok(Self::Drop),
+            other => Err(format!("Unknown redaction mode: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct RedactionPolicy {
+    mode: RedactionMode,
+    salt: String,
+    query_params: Vec<String>,
+}
+
+static POLICY: OnceLock<RedactionPolicy> = OnceLock::new();
+
+impl RedactionPolicy {
+    fn from_config(config: &RedactionConfig) -> Result<Self, String> {
+        Ok(Self {
+            mode: config.mode.parse()?,
+            salt: config.salt.clone(),
+            query_params: config.query_params.clone(),
+        })
+    }
+}
+
+/// Installs the redaction policy; must run before the first PII field is logged.
+pub fn init(config: &RedactionConfig) -> anyhow::Result<()> {
+    let policy = RedactionPolicy::from_config(config).map_err(anyhow::Error::msg)?;
+    POLICY
+        .set(policy)
+        .map_err(|_| anyhow::anyhow!("Redaction policy already initialized"))
+}
+
+fn policy() -> &'static RedactionPolicy {
+    POLICY.get_or_init(|| {
+        RedactionPolicy::from_config(&RedactionConfig::default()).unwrap_or(RedactionPolicy {
+            mode: RedactionMode::Mask,
+            salt: String::new(),
+            query_params: Vec::new(),
+        })
+    })
+}
+
+/// Tags an email address as PII for use as a tracing field value.
+///
+/// Returns `None` in drop mode, which tracing records as an absent field:
+/// `tracing::info!(user_email = redaction::email(&user.email), "...")`.
+pub fn email(value: &str) -> Option<DisplayValue<String>> {
+    redact(value, mask_email)
+}
+
+/// Tags a personal name or other free-form PII as a tracing field value.
+pub fn pii(value: &str) -> Option<DisplayValue<String>> {
+    redact(value, mask_text)
+}
+
+fn redact(value: &str, mask: fn(&str) -> String) -> Option<DisplayValue<String>> {
+    let policy = policy();
+    match policy.mode {
+        RedactionMode::Off => Some(display(value.to_string())),
+        RedactionMode::Hash => Some(display(hash(&policy.salt, value))),
+        RedactionMode::Mask => Some(display(mask(value))),
+        RedactionMode::Drop => None,
+    }
+}
+
+/// Whether PII is redacted at all, i.e. the mode is not "off".
+pub fn enabled() -> bool {
+    policy().mode != RedactionMode::Off
+}
+
+/// Redacts the values of configured PII query parameters, leaving the path
+/// and other parameters intact.
+pub fn uri(uri: &axum::http::Uri) -> String {
+    let full = uri.to_string();
+    match full.split_once('?') {
+        Some((base, query)) => with_query(base, query),
+        None => full,
+    }
+}
+
+/// Like [`uri`], but returns only the origin-form request target (path and query).
+pub fn request_target(uri: &axum::http::Uri) -> String {
+    match uri.query() {
+        Some(query) => with_query(uri.path(), query),
+        None => uri.path().to_string(),
+    }
+}
+
+/// Redacts PII parameters in an `application/x-www-form-urlencoded` body.
+pub fn form(body: &str) -> String {
+    query_pairs(body).join("&")
+}
+
+fn with_query(base: &str, query: &str) -> String {
+    let pairs = query_pairs(query);
+    if pairs.is_empty() {
+        base.to_string()
+    } else {
+        format!("{}?{}", base, pairs.join("&"))
+    }
+}
+
+fn query_pairs(query: &str) -> Vec<String> {
+    let policy = policy();
+    if policy.mode == RedactionMode::Off {
+        return vec![query.to_string()];
+    }
+
+    query
+        .split('&')
+        .filter_map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            if !policy.query_params.iter().any(|p| p.eq_ignore_ascii_case(key)) {
+                return Some(pair.to_string());
+            }
+            match policy.mode {
+                RedactionMode::Off => Some(pair.to_string()),
+                RedactionMode::Hash => Some(format!("{}={}", key, hash(&policy.salt, value))),
+                RedactionMode::Mask => Some(format!("{}={}", key, mask_text(value))),
+                RedactionMode::Drop => None,
+            }
+        })
+        .collect()
+}
+
+fn hash(salt: &str, value: &str) -> String {
+    let digest = Sha256::new()
+        .chain_update(salt.as_bytes())
+        .chain_update(value.as_bytes())
+        .finalize();
+    let hex: String = digest.iter().take(8).map(|byte| format!("{:02x}", byte)).collect();
+    format!("sha256:{}", hex)
+}
+
+fn mask_text(value: &str) -> String {
+    match value.chars().next() {
+        Some(first) => format!("{}***", first),
+        None => String::new(),
+    }
+}
+
+fn mask_email(value: &str) -> String {
+    match value.rsplit_once('@') {
+        Some((local, domain)) => format!("{}@{}", mask_text(local), domain),
+        None => mask_text(value),
+    }
+}
@@ -0,0 +1,239 @@
+use anyhow::{Context as _, Result};
+use std::{collections::HashSet, fmt, path::Path};
+use thiserror::Error;
+
+// RFC 5321 section 4.5.3.1: the local part is limited to 64 octets and a path
+// to 256, which leaves 254 for the address between the angle brackets
+const MAX_LOCAL_PART_LEN: usize = 64;
+const MAX_ADDRESS_LEN: usize = 254;
+const LOCAL_PART_SPECIALS: &str = "!#$%&'*+-/=?^_`{|}~";
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum EmailError {
+    #[error("must contain a local part and a domain separated by '@'")]
+    MissingAt,
+    #[error("local part must not be empty")]
+    EmptyLocalPart,
+    #[error("local part must be at most 64 bytes")]
+    LocalPartTooLong,
+    #[error("local part must not start or end with a dot or contain consecutive dots")]
+    MisplacedDot,
+    #[error("local part contains an invalid character '{0}'")]
+    InvalidCharacter(char),
+    #[error("domain is not a valid internet domain name")]
+    InvalidDomain,
+    #[error("must be at most 254 bytes")]
+    TooLong,
+}
+
+/// An email address that follows the practical subset of RFC 5321/5322 that
+/// mail servers accept: a dot-atom local part and a fully qualified domain.
+/// Internationalized local parts (RFC 6531) and domains (IDNA) are allowed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmailAddress {
+    local: String,
+    domain: String,
+    ascii_domain: String,
+}
+
+impl EmailAddress {
+    /// Parses and normalizes an address. Only the domain is case-folded: the
+    /// local part is case-sensitive per RFC 5321 and is kept as given.
+    pub fn parse(input: &str) -> Result<Self, EmailError> {
+        let (local, domain) = input.trim().rsplit_once('@').ok_or(EmailError::MissingAt)?;
+        validate_local_part(local)?;
+
+        let ascii_domain = idna::domain_to_ascii_strict(domain).map_err(|_| EmailError::InvalidDomain)?;
+        // Require a fully qualified name and reject IPv4-looking domains
+        let top_level = ascii_domain.rsplit('.').next().unwrap_or_default();
+        if !ascii_domain.contains('.') || top_level.chars().all(|c| c.is_ascii_digit()) {
+            return Err(EmailError::InvalidDomain);
+        }
+        if local.len() + 1 + ascii_domain.len() > MAX_ADDRESS_LEN {
+            return Err(EmailError::TooLong);
+        }
+
+        let (domain, result) = idna::domain_to_unicode(&ascii_domain);
+        result.map_err(|_| EmailError::InvalidDomain)?;
+
+        Ok(Self {
+            local: local.to_string(),
+            domain,
+            ascii_domain,
+        })
+    }
+
+    /// The domain in its lowercase Unicode form, e.g. `bücher.example`.
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// The domain as sent over SMTP, e.g. `xn--bcher-kva.example`.
+    pub fn ascii_domain(&self) -> &str {
+        &self.ascii_domain
+    }
+}
+
+impl fmt::Display for EmailAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.local, self.domain)
+    }
+}
+
+fn validate_local_part(local: &str) -> Result<(), EmailError> {
+    if local.is_empty() {
+        return Err(EmailError::EmptyLocalPart);
+    }
+    if local.len() > MAX_LOCAL_PART_LEN {
+        return Err(EmailError::LocalPartTooLong);
+    }
+    if local.starts_with('.') || local.ends_with('.') || local.contains("..") {
+        return Err(EmailError::MisplacedDot);
+    }
+    // Quoted local parts are valid RFC 5322 but rejected by most providers and forms
+    match local
+        .chars()
+        .find(|&c| !(c.is_ascii_alphanumeric() || c == '.' || LOCAL_PART_SPECIALS.contains(c) || is_utf8_atext(c)))
+    {
+        Some(c) => Err(EmailError::InvalidCharacter(c)),
+        None => Ok(()),
+    }
+}
+
+/// Non-ASCII characters allowed by RFC 6531, excluding invisible ones.
+fn is_utf8_atext(c: char) -> bool {
+    !c.is_ascii() && !c.is_control() && !c.is_whitespace()
+}
+
+/// Domains of disposable mailbox providers. A listed domain also blocks its
+/// subdomains.
+#[derive(Debug, Default)]
+pub struct DomainBlocklist {
+    domains: HashSet<String>,
+}
+
+impl DomainBlocklist {
+    /// Loads one domain per line; blank lines and `#` comments are ignored.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read email domain blocklist {}", path.display()))?;
+
+        let domains = contents
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default().trim())
+            .filter(|line| !line.is_empty())
+            .map(|domain| {
+                idna::domain_to_ascii(domain).with_context(|| format!("Invalid domain in blocklist: {}", domain))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { domains })
+    }
+
+    pub fn len(&self) -> usize {
+        self.domains.len()
+    }
+
+    pub fn contains(&self, email: &EmailAddress) -> bool {
+        let mut domain = email.ascii_domain();
+        loop {
+            if self.domains.contains(domain) {
+                return true;
+            }
+            match domain.split_once('.') {
+                Some((_, parent)) => domain = parent,
+                None => return false,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocklist(domains: &[&str]) -> DomainBlocklist {
+        DomainBlocklist {
+            domains: domains.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn rejects_addresses_made_of_at_signs() {
+        assert_eq!(EmailAddress::parse("@@@@@"), Err(EmailError::InvalidCharacter('@')));
+        assert_eq!(EmailAddress::parse("@example.com"), Err(EmailError::EmptyLocalPart));
+        assert_eq!(EmailAddress::parse("jane.example.com"), Err(EmailError::MissingAt));
+    }
+
+    #[test]
+    fn limits_the_local_part_to_64_bytes() {
+        let domain = "example.com";
+        assert!(EmailAddress::parse(&format!("{}@{}", "a".repeat(64), domain)).is_ok());
+        assert_eq!(
+            EmailAddress::parse(&format!("{}@{}", "a".repeat(65), domain)),
+            Err(EmailError::LocalPartTooLong)
+        );
+        // Bytes, not characters: 33 two-byte characters are 66 bytes
+        assert_eq!(
+            EmailAddress::parse(&format!("{}@{}", "é".repeat(33), domain)),
+            Err(EmailError::LocalPartTooLong)
+        );
+    }
+
+    #[test]
+    fn limits_the_address_to_254_bytes() {
+        // 64 + 1 + 189 = 254, with labels of at most 63 bytes
+        let domain = |len: usize| {
+            let label = "b".repeat(61);
+            let rest = "c".repeat(len - 2 * 62 - 4);
+            format!("{label}.{label}.{rest}.com")
+        };
+        let local = "a".repeat(64);
+        assert_eq!(domain(189).len(), 189);
+
+        assert!(EmailAddress::parse(&format!("{}@{}", local, domain(189))).is_ok());
+        assert_eq!(
+            EmailAddress::parse(&format!("{}@{}", local, domain(190))),
+            Err(EmailError::TooLong)
+        );
+    }
+
+    #[test]
+    fn rejects_misplaced_dots_in_the_local_part() {
+        for input in [".jane@example.com", "jane.@example.com", "ja..ne@example.com"] {
+            assert_eq!(EmailAddress::parse(input), Err(EmailError::MisplacedDot), "{}", input);
+        }
+        assert!(EmailAddress::parse("jane.doe@example.com").is_ok());
+    }
+
+    #[test]
+    fn round_trips_internationalized_domains() {
+        let unicode = EmailAddress::parse("Jane@Bücher.Example").unwrap();
+        assert_eq!(unicode.domain(), "bücher.example");
+        assert_eq!(unicode.ascii_domain(), "xn--bcher-kva.example");
+        assert_eq!(unicode.to_string(), "Jane@bücher.example");
+
+        let ascii = EmailAddress::parse("Jane@xn--bcher-kva.example").unwrap();
+        assert_eq!(ascii, unicode);
+    }
+
+    #[test]
+    fn rejects_all_numeric_top_level_domains() {
+        assert_eq!(EmailAddress::parse("jane@192.168.0.1"), Err(EmailError::InvalidDomain));
+        assert_eq!(EmailAddress::parse("jane@example.123"), Err(EmailError::InvalidDomain));
+        assert_eq!(EmailAddress::parse("jane@localhost"), Err(EmailError::InvalidDomain));
+        assert!(EmailAddress::parse("jane@example.x1").is_ok());
+    }
+
+    #[test]
+    fn blocks_listed_domains_and_their_subdomains() {
+        let blocklist = blocklist(&["mailinator.com", "xn--bcher-kva.example"]);
+        let blocked = |input: &str| blocklist.contains(&EmailAddress::parse(input).unwrap());
+
+        assert!(blocked("jane@mailinator.com"));
+        assert!(blocked("jane@eu.mx.mailinator.com"));
+        assert!(blocked("jane@bücher.example"));
+        assert!(!blocked("jane@notmailinator.com"));
+        assert!(!blocked("jane@mailinator.com.example"));
+    }
+}
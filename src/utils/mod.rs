@@ -1,7 +1,4 @@
-// Utility modules for common functionality
-// Currently empty but ready for future utilities like:
-// - Date/time helpers
-// - String utilities
-// - Encryption/hashing
-// - Email validation
-// - etc.
\ No newline at end of file
+pub mod email;
+pub mod network;
+pub mod sync;
+pub mod text;
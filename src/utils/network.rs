@@ -0,0 +1,20 @@
+use ipnet::IpNet;
+use std::net::IpAddr;
+
+/// Parses a CIDR range such as `10.0.0.0/8`, or a single address as a host route.
+pub fn parse_network(value: &str) -> Result<IpNet, String> {
+    let value = value.trim();
+    value
+        .parse::<IpNet>()
+        .or_else(|_| value.parse::<IpAddr>().map(IpNet::from))
+        .map_err(|_| format!("Invalid address or CIDR range: {}", value))
+}
+
+/// Whether `path` is `prefix` or lies below it, so `/api/users` covers
+/// `/api/users/1` but not `/api/usersX`.
+pub fn path_has_prefix(path: &str, prefix: &str) -> bool {
+    match path.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || prefix.ends_with('/') || rest.starts_with('/'),
+        None => false,
+    }
+}
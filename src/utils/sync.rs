@@ -0,0 +1,13 @@
+use std::sync::{Mutex, MutexGuard};
+
+/// Locks `mutex`, recovering the guard if a panic poisoned it.
+///
+/// Only for state that is consistent between statements, such as buffers and
+/// job records; `what` names the state in the warning.
+pub fn lock_recover<'a, T>(mutex: &'a Mutex<T>, what: &str) -> MutexGuard<'a, T> {
+    mutex.lock().unwrap_or_else(|poisoned| {
+        tracing::warn!("{} lock was poisoned by a panic, recovering", what);
+        mutex.clear_poison();
+        poisoned.into_inner()
+    })
+}
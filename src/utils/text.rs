@@ -0,0 +1,39 @@
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Trims surrounding whitespace and composes the text to NFC, so that visually
+/// identical names are stored, compared and counted the same way.
+pub fn normalize(value: &str) -> String {
+    value.trim().nfc().collect()
+}
+
+/// Length as a reader perceives it: `é` written as `e` plus a combining accent,
+/// or a family emoji made of several code points, each count as one.
+pub fn grapheme_count(value: &str) -> usize {
+    value.graphemes(true).count()
+}
+
+/// The most combining marks carried by any one grapheme. Real scripts stack a
+/// few, e.g. a Devanagari conjunct with its vowel sign; text piling up dozens
+/// on a letter is meant to overflow the lines around it.
+pub fn max_marks_per_grapheme(value: &str) -> usize {
+    value
+        .graphemes(true)
+        .map(|grapheme| grapheme.chars().filter(|&c| is_combining_mark(c)).count())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Returns the first control character or bidirectional formatting character.
+/// The latter can reorder the surrounding text on display and are a common way
+/// to make a name look like another one.
+pub fn find_forbidden_char(value: &str) -> Option<char> {
+    value.chars().find(|&c| c.is_control() || is_bidi_control(c))
+}
+
+fn is_bidi_control(c: char) -> bool {
+    matches!(
+        c,
+        '\u{061C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}'
+    )
+}